@@ -0,0 +1,97 @@
+//! Compares finding paths on the navigation graph against building the movement graph for each query.
+//!
+//! Run with `cargo bench -p game_plugin --bench pathfinding`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use game_plugin::{
+    hex::HexCoord,
+    pathfinding::{MovementGraph, MovementType, find_path, navigation::NavigationGraph},
+    worldgen::{WorldData, WorldGenSettings, generate_world},
+};
+
+/// Number of start and goal pairs each benchmark iteration finds paths between
+const PATH_COUNT: usize = 20;
+/// Seed of the benchmarked world, which needs at least two landmasses
+const SEED: u32 = 1234;
+
+/// Tiles of the landmasses a land unit can stand on, biggest landmass first
+fn landmass_tiles(world: &WorldData) -> Vec<Vec<HexCoord>> {
+    let mut landmasses: Vec<Vec<HexCoord>> = world.landmasses.landmasses
+        .iter()
+        .map(|landmass| landmass.tiles
+            .iter()
+            .map(|&(x, y)| HexCoord::new(x, y))
+            .filter(|tile| MovementType::Land.terrain_cost(world.tile(tile.x, tile.y).terrain_type).is_some())
+            .collect())
+        .collect();
+    landmasses.sort_by_key(|tiles: &Vec<HexCoord>| std::cmp::Reverse(tiles.len()));
+    landmasses
+}
+
+/// Pairs of far apart tiles on the biggest landmass that a land unit can travel between
+fn land_routes(landmasses: &[Vec<HexCoord>], movement_graph: &MovementGraph) -> Vec<(HexCoord, HexCoord)> {
+    let tiles = landmasses.first().unwrap_or_else(|| panic!("seed {} has no landmasses", SEED));
+    let step = (tiles.len() / (PATH_COUNT * 2)).max(1);
+    (0..tiles.len() / 2)
+        .step_by(step)
+        .map(|i| (tiles[i], tiles[tiles.len() - 1 - i]))
+        .filter(|&(start, goal)| movement_graph.find_path(start, goal).is_some())
+        .take(PATH_COUNT)
+        .collect()
+}
+
+/// Pairs of tiles from the biggest landmass to the second biggest, which a land unit can't travel between
+fn overseas_routes(landmasses: &[Vec<HexCoord>]) -> Vec<(HexCoord, HexCoord)> {
+    let (from, to) = match landmasses {
+        [from, to, ..] => (from, to),
+        _ => panic!("seed {} has fewer than two landmasses", SEED),
+    };
+    (0..PATH_COUNT)
+        .map(|i| (from[i * from.len() / PATH_COUNT], to[i * to.len() / PATH_COUNT]))
+        .collect()
+}
+
+fn bench_routes(c: &mut Criterion, name: &str, routes: &[(HexCoord, HexCoord)], world: &WorldData, movement_graph: &MovementGraph, navigation: &NavigationGraph) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    group.bench_function("naive A*", |b| b.iter(|| {
+        for &(start, goal) in routes.iter() {
+            find_path(world, start, goal, MovementType::Land);
+        }
+    }));
+    group.bench_function("cached movement graph", |b| b.iter(|| {
+        for &(start, goal) in routes.iter() {
+            movement_graph.find_path(start, goal);
+        }
+    }));
+    group.bench_function("navigation graph", |b| b.iter(|| {
+        for &(start, goal) in routes.iter() {
+            navigation.find_path(start, goal);
+        }
+    }));
+    group.finish();
+}
+
+fn pathfinding_benchmark(c: &mut Criterion) {
+    let world = generate_world(WorldGenSettings { seed: SEED, ..Default::default() });
+    let movement_graph = MovementGraph::new(&world, MovementType::Land);
+    let navigation = NavigationGraph::new(&world, MovementType::Land);
+    let landmasses = landmass_tiles(&world);
+    let routes = land_routes(&landmasses, &movement_graph);
+    bench_routes(c, "land paths", &routes, &world, &movement_graph, &navigation);
+    bench_routes(c, "unreachable goals", &overseas_routes(&landmasses), &world, &movement_graph, &navigation);
+
+    let mut group = c.benchmark_group("navigation updates");
+    group.sample_size(10);
+    let &(changed, _) = routes.first().unwrap_or_else(|| panic!("seed {} has no land routes", SEED));
+    group.bench_function("update one tile", |b| b.iter_batched_ref(
+        || NavigationGraph::new(&world, MovementType::Land),
+        |navigation| navigation.update_tile(&world, changed),
+        criterion::BatchSize::LargeInput,
+    ));
+    group.bench_function("rebuild", |b| b.iter(|| NavigationGraph::new(&world, MovementType::Land)));
+    group.finish();
+}
+
+criterion_group!(benches, pathfinding_benchmark);
+criterion_main!(benches);
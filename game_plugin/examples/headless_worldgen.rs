@@ -0,0 +1,44 @@
+//! Generates a world in a headless Bevy app, without a window or GPU.
+//!
+//! Usage: `cargo run -p game_plugin --no-default-features --example headless_worldgen -- [seed]`
+
+use bevy::{app::AppExit, prelude::*};
+use game_plugin::worldgen::{GenerateWorldEvent, WorldData, WorldGenPlugin, WorldGenSettings};
+
+fn main() {
+    let mut settings = WorldGenSettings::default();
+    if let Some(seed) = std::env::args().nth(1) {
+        settings.seed = seed.parse().expect("seed must be a number");
+    }
+
+    App::build()
+        .add_plugins(MinimalPlugins)
+        .add_plugin(WorldGenPlugin)
+        .insert_resource(settings)
+        .add_startup_system(request_world.system())
+        .add_system(report_world.system())
+        .run();
+}
+
+fn request_world(mut events: EventWriter<GenerateWorldEvent>) {
+    events.send(GenerateWorldEvent);
+}
+
+fn report_world(world: Option<Res<WorldData>>, mut exit: EventWriter<AppExit>) {
+    if let Some(world) = world {
+        let land_tiles = (0..world.width)
+            .flat_map(|x| (0..world.height).map(move |y| (x, y)))
+            .filter(|&(x, y)| !world.tile(x, y).terrain_type.is_water())
+            .count();
+        println!(
+            "Seed {}: {}x{} tiles, {:.1}% land, {} landmasses, {} water bodies",
+            world.settings.seed,
+            world.width,
+            world.height,
+            land_tiles as f64 / (world.width * world.height) as f64 * 100.0,
+            world.landmasses.landmasses.len(),
+            world.water_bodies.bodies.len(),
+        );
+        exit.send(AppExit);
+    }
+}
@@ -0,0 +1,288 @@
+//! The in-game calendar.
+//!
+//! Years have the twelve months of the Gregorian calendar but no leap days, so every year is
+//! `DAYS_IN_YEAR` days long. Years before the common era are negative, and like the historical
+//! calendar there is no year 0: 1 BCE is followed by 1 CE.
+
+use std::{fmt, ops::{Add, AddAssign, Sub}};
+
+pub const DAYS_IN_YEAR: u32 = 365;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Month {
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+impl Month {
+    pub const ALL: [Month; 12] = [
+        Month::January,
+        Month::February,
+        Month::March,
+        Month::April,
+        Month::May,
+        Month::June,
+        Month::July,
+        Month::August,
+        Month::September,
+        Month::October,
+        Month::November,
+        Month::December,
+    ];
+
+    pub fn days(self) -> u32 {
+        match self {
+            Month::February => 28,
+            Month::April | Month::June | Month::September | Month::November => 30,
+            _ => 31,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Month::January => "January",
+            Month::February => "February",
+            Month::March => "March",
+            Month::April => "April",
+            Month::May => "May",
+            Month::June => "June",
+            Month::July => "July",
+            Month::August => "August",
+            Month::September => "September",
+            Month::October => "October",
+            Month::November => "November",
+            Month::December => "December",
+        }
+    }
+
+    /// Days in the year before the first of this month
+    fn days_before(self) -> u32 {
+        Month::ALL.iter().take_while(|&&month| month != self).map(|month| month.days()).sum()
+    }
+}
+
+/// A day on the calendar
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GameDate {
+    // field order makes the derived ordering chronological
+    year: i32,
+    month: Month,
+    day: u32,
+}
+
+impl GameDate {
+    /// The date for a day of a month, starting from 1. Negative years are BCE.
+    ///
+    /// Panics if the day isn't in the month or the year is 0.
+    pub fn new(day: u32, month: Month, year: i32) -> Self {
+        assert!(year != 0, "there is no year 0");
+        assert!((1..=month.days()).contains(&day), "{} has no day {}", month.name(), day);
+        GameDate { year, month, day }
+    }
+
+    /// 1 January of a year
+    pub fn start_of_year(year: i32) -> Self {
+        GameDate::new(1, Month::January, year)
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn month(&self) -> Month {
+        self.month
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn is_bce(&self) -> bool {
+        self.year < 0
+    }
+
+    pub fn is_start_of_month(&self) -> bool {
+        self.day == 1
+    }
+
+    pub fn is_start_of_year(&self) -> bool {
+        self.day == 1 && self.month == Month::January
+    }
+
+    /// Day of the year, starting from 0 on 1 January
+    pub fn day_of_year(&self) -> u32 {
+        self.month.days_before() + self.day - 1
+    }
+
+    /// Days since 1 January 1 CE, negative for earlier dates
+    fn days_since_epoch(&self) -> i64 {
+        // skip the year 0 that doesn't exist
+        let years = if self.year > 0 { self.year as i64 - 1 } else { self.year as i64 };
+        years * DAYS_IN_YEAR as i64 + self.day_of_year() as i64
+    }
+
+    fn from_days_since_epoch(days: i64) -> Self {
+        let years = days.div_euclid(DAYS_IN_YEAR as i64);
+        let mut day_of_year = days.rem_euclid(DAYS_IN_YEAR as i64) as u32;
+        let year = if years >= 0 { years + 1 } else { years };
+        for &month in Month::ALL.iter() {
+            if day_of_year < month.days() {
+                return GameDate::new(day_of_year + 1, month, year as i32);
+            }
+            day_of_year -= month.days();
+        }
+        unreachable!("day of year is always less than DAYS_IN_YEAR")
+    }
+
+    /// The date a number of days later, or earlier if `days` is negative
+    pub fn add_days(&self, days: i64) -> Self {
+        GameDate::from_days_since_epoch(self.days_since_epoch() + days)
+    }
+
+    /// The date a number of months later, or earlier if `months` is negative.
+    /// The day is clamped to the end of shorter months.
+    pub fn add_months(&self, months: i32) -> Self {
+        let month_index = self.month as i32 + months;
+        let month = Month::ALL[month_index.rem_euclid(12) as usize];
+        let years = month_index.div_euclid(12);
+        GameDate::new(self.day.min(month.days()), month, self.add_years_to(years))
+    }
+
+    /// The same day a number of years later, or earlier if `years` is negative
+    pub fn add_years(&self, years: i32) -> Self {
+        GameDate::new(self.day, self.month, self.add_years_to(years))
+    }
+
+    fn add_years_to(&self, years: i32) -> i32 {
+        let year = self.year + years;
+        // crossing between the eras skips the year 0
+        if self.year > 0 && year <= 0 {
+            year - 1
+        } else if self.year < 0 && year >= 0 {
+            year + 1
+        } else {
+            year
+        }
+    }
+
+    pub fn next_day(&self) -> Self {
+        self.add_days(1)
+    }
+
+    /// Days from another date to this one, negative if the other date is later
+    pub fn days_since(&self, other: GameDate) -> i64 {
+        self.days_since_epoch() - other.days_since_epoch()
+    }
+}
+
+impl Default for GameDate {
+    fn default() -> Self {
+        GameDate::start_of_year(1)
+    }
+}
+
+impl Add<i64> for GameDate {
+    type Output = GameDate;
+
+    fn add(self, days: i64) -> GameDate {
+        self.add_days(days)
+    }
+}
+
+impl AddAssign<i64> for GameDate {
+    fn add_assign(&mut self, days: i64) {
+        *self = self.add_days(days);
+    }
+}
+
+impl Sub for GameDate {
+    type Output = i64;
+
+    /// Days between two dates
+    fn sub(self, other: GameDate) -> i64 {
+        self.days_since(other)
+    }
+}
+
+impl fmt::Display for GameDate {
+    /// Formats as "14 March 1444", or "15 March 44 BCE" before the common era
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_bce() {
+            write!(f, "{} {} {} BCE", self.day, self.month.name(), -self.year)
+        } else {
+            write!(f, "{} {} {}", self.day, self.month.name(), self.year)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn month_lengths_add_up_to_a_year() {
+        assert_eq!(Month::ALL.iter().map(|month| month.days()).sum::<u32>(), DAYS_IN_YEAR);
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(GameDate::new(14, Month::March, 1444).to_string(), "14 March 1444");
+        assert_eq!(GameDate::new(15, Month::March, -44).to_string(), "15 March 44 BCE");
+    }
+
+    #[test]
+    fn adding_days_rolls_over_months_and_years() {
+        let date = GameDate::new(31, Month::January, 1444);
+        assert_eq!(date.next_day(), GameDate::new(1, Month::February, 1444));
+        assert_eq!(GameDate::new(31, Month::December, 1444).next_day(), GameDate::start_of_year(1445));
+        assert_eq!(date + DAYS_IN_YEAR as i64, GameDate::new(31, Month::January, 1445));
+        assert_eq!(date.add_days(-31), GameDate::new(31, Month::December, 1443));
+    }
+
+    #[test]
+    fn start_of_months_and_years() {
+        let year_end = GameDate::new(31, Month::December, 1444);
+        assert!(!year_end.is_start_of_month());
+        assert!(year_end.next_day().is_start_of_month() && year_end.next_day().is_start_of_year());
+        let march = GameDate::new(1, Month::March, 1445);
+        assert!(march.is_start_of_month() && !march.is_start_of_year());
+    }
+
+    #[test]
+    fn there_is_no_year_zero() {
+        assert_eq!(GameDate::new(31, Month::December, -1).next_day(), GameDate::start_of_year(1));
+        assert_eq!(GameDate::start_of_year(1).add_days(-1), GameDate::new(31, Month::December, -1));
+        assert_eq!(GameDate::start_of_year(3).add_years(-3), GameDate::start_of_year(-1));
+        assert_eq!(GameDate::start_of_year(-2).add_years(2), GameDate::start_of_year(1));
+        assert_eq!(GameDate::start_of_year(1) - GameDate::start_of_year(-1), DAYS_IN_YEAR as i64);
+    }
+
+    #[test]
+    fn adding_months_clamps_the_day() {
+        let date = GameDate::new(31, Month::January, 1444);
+        assert_eq!(date.add_months(1), GameDate::new(28, Month::February, 1444));
+        assert_eq!(date.add_months(12), GameDate::new(31, Month::January, 1445));
+        assert_eq!(date.add_months(-1), GameDate::new(31, Month::December, 1443));
+    }
+
+    #[test]
+    fn days_between_dates() {
+        let start = GameDate::new(11, Month::November, 1444);
+        let end = GameDate::new(14, Month::March, 1445);
+        assert_eq!(end - start, 19 + 31 + 31 + 28 + 14);
+        assert_eq!(start.days_since(end), -(end - start));
+        assert_eq!(start + (end - start), end);
+        assert!(start < end);
+        assert!(GameDate::start_of_year(-500) < GameDate::start_of_year(-499));
+    }
+}
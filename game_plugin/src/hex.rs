@@ -0,0 +1,623 @@
+//! Coordinates and geometry of tiles on the hex map.
+//!
+//! Tiles are addressed by offset coordinates, where `x` is the column and `y` the row. Rows count
+//! upwards like world space. The map is made of flat-topped hexes laid out by the tilemap's
+//! `ColumnEven` mesher, which moves even columns down and odd columns up by a quarter of a hex,
+//! so odd columns sit half a tile higher than even ones.
+
+use bevy::math::Vec2;
+use std::ops::{Add, Mul};
+use chickenwire::coordinate::{MultiCoord, Offset};
+
+/// Size of a hex in the tileset and in world space
+pub const HEX_WIDTH: f32 = 32.0;
+pub const HEX_HEIGHT: f32 = 32.0;
+/// Flat-topped hexes in neighboring columns overlap by a quarter of their width
+pub const COLUMN_SPACING: f32 = HEX_WIDTH * 0.75;
+/// How far the mesher moves even columns down and odd columns up
+const COLUMN_SHIFT: f32 = HEX_HEIGHT / 4.0;
+
+/// Offset coordinate of a tile, the same as the tilemap's `MapVec2` and `WorldData::tile`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct HexCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Axial coordinate, with `q` the column and `r` increasing towards the south
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct AxialCoord {
+    pub q: i32,
+    pub r: i32,
+}
+
+/// Cube coordinate, always satisfying `q + r + s == 0`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CubeCoord {
+    pub q: i32,
+    pub r: i32,
+    pub s: i32,
+}
+
+/// Edges of a flat-topped hex, in clockwise order
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HexDirection {
+    North = 0,
+    NorthEast = 1,
+    SouthEast = 2,
+    South = 3,
+    SouthWest = 4,
+    NorthWest = 5,
+}
+
+impl HexDirection {
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::North,
+        HexDirection::NorthEast,
+        HexDirection::SouthEast,
+        HexDirection::South,
+        HexDirection::SouthWest,
+        HexDirection::NorthWest,
+    ];
+
+    pub fn opposite(self) -> HexDirection {
+        HexDirection::ALL[(self as usize + 3) % 6]
+    }
+
+    /// Offset to the neighbor in this direction from a tile in column `x`.
+    /// Odd columns sit half a tile higher than even ones, so their eastern and western
+    /// neighbors are a row further up.
+    pub fn offset(self, x: i32) -> (i32, i32) {
+        let even = x % 2 == 0;
+        match self {
+            HexDirection::North => (0, 1),
+            HexDirection::South => (0, -1),
+            HexDirection::NorthEast => if even { (1, 0) } else { (1, 1) },
+            HexDirection::SouthEast => if even { (1, -1) } else { (1, 0) },
+            HexDirection::SouthWest => if even { (-1, -1) } else { (-1, 0) },
+            HexDirection::NorthWest => if even { (-1, 0) } else { (-1, 1) },
+        }
+    }
+
+    /// Step in this direction in cube coordinates, which is the same from every tile
+    pub fn cube(self) -> CubeCoord {
+        let (q, r) = match self {
+            HexDirection::North => (0, -1),
+            HexDirection::NorthEast => (1, -1),
+            HexDirection::SouthEast => (1, 0),
+            HexDirection::South => (0, 1),
+            HexDirection::SouthWest => (-1, 1),
+            HexDirection::NorthWest => (-1, 0),
+        };
+        AxialCoord { q, r }.to_cube()
+    }
+}
+
+impl HexCoord {
+    pub const fn new(x: i32, y: i32) -> Self {
+        HexCoord { x, y }
+    }
+
+    pub fn to_axial(self) -> AxialCoord {
+        // each pair of columns moves the axial row down by one, and rows in axial point down.
+        // odd columns are higher, so they start the next pair
+        AxialCoord {
+            q: self.x,
+            r: -self.y - (self.x + (self.x & 1)) / 2,
+        }
+    }
+
+    pub fn from_axial(axial: AxialCoord) -> Self {
+        HexCoord {
+            x: axial.q,
+            y: -axial.r - (axial.q + (axial.q & 1)) / 2,
+        }
+    }
+
+    pub fn to_cube(self) -> CubeCoord {
+        self.to_axial().to_cube()
+    }
+
+    pub fn from_cube(cube: CubeCoord) -> Self {
+        HexCoord::from_axial(cube.to_axial())
+    }
+
+    /// The neighboring tile in a direction, ignoring map bounds
+    pub fn neighbor(self, direction: HexDirection) -> HexCoord {
+        let (dx, dy) = direction.offset(self.x);
+        HexCoord::new(self.x + dx, self.y + dy)
+    }
+
+    /// All six neighbors in `HexDirection::ALL` order, ignoring map bounds
+    pub fn neighbors(self) -> impl Iterator<Item = HexCoord> {
+        HexDirection::ALL.iter().map(move |&direction| self.neighbor(direction))
+    }
+
+    /// Number of steps between two tiles, ignoring map bounds and wrapping
+    pub fn distance(self, other: HexCoord) -> i32 {
+        self.to_cube().distance(other.to_cube())
+    }
+
+    /// Tiles exactly `radius` steps away, going clockwise from the south-west.
+    /// A radius of zero is just this tile.
+    pub fn ring(self, radius: i32) -> impl Iterator<Item = HexCoord> {
+        let start = self.to_cube() + HexDirection::SouthWest.cube() * radius;
+        // walk each of the six sides of the ring, `radius` steps at a time
+        let steps = HexDirection::ALL
+            .iter()
+            .flat_map(move |&direction| (0..radius).map(move |_| direction.cube()));
+        let ring = steps.scan(start, |current, step| {
+            let tile = *current;
+            *current = *current + step;
+            Some(HexCoord::from_cube(tile))
+        });
+        let center = if radius == 0 { Some(self) } else { None };
+        center.into_iter().chain(ring)
+    }
+
+    /// Tiles within `radius` steps, spiralling out from this tile ring by ring
+    pub fn spiral(self, radius: i32) -> impl Iterator<Item = HexCoord> {
+        (0..=radius).flat_map(move |ring_radius| self.ring(ring_radius))
+    }
+
+    /// Tiles within `radius` steps, in column order. Cheaper than `spiral` when order doesn't matter.
+    pub fn range(self, radius: i32) -> impl Iterator<Item = HexCoord> {
+        let center = self.to_cube();
+        (-radius..=radius).flat_map(move |dq| {
+            let min_r = (-radius).max(-dq - radius);
+            let max_r = radius.min(-dq + radius);
+            (min_r..=max_r).map(move |dr| HexCoord::from_cube(center + AxialCoord { q: dq, r: dr }.to_cube()))
+        })
+    }
+
+    /// Tiles on the straight line between two tiles, including both ends.
+    /// Consecutive tiles are always neighbors.
+    pub fn line_to(self, other: HexCoord) -> Vec<HexCoord> {
+        let distance = self.distance(other);
+        if distance == 0 {
+            return vec![self];
+        }
+        let (start, end) = (self.to_cube(), other.to_cube());
+        // nudged off the exact edges between hexes so ties always round the same way
+        let (start_q, start_r) = (start.q as f32 + 1e-6, start.r as f32 + 2e-6);
+        let (end_q, end_r) = (end.q as f32 + 1e-6, end.r as f32 + 2e-6);
+        (0..=distance)
+            .map(|step| {
+                let t = step as f32 / distance as f32;
+                let q = start_q + (end_q - start_q) * t;
+                let r = start_r + (end_r - start_r) * t;
+                HexCoord::from_cube(CubeCoord::round(q, r))
+            })
+            .collect()
+    }
+
+    /// Center of the tile in the local space of the tilemap, where the mesher draws it
+    pub fn to_world_position(self) -> Vec2 {
+        Vec2::new(
+            self.x as f32 * COLUMN_SPACING + HEX_WIDTH / 2.0,
+            self.y as f32 * HEX_HEIGHT + HEX_HEIGHT / 2.0 + column_shift(self.x),
+        )
+    }
+
+    /// The tile containing a position in the local space of the tilemap
+    pub fn from_world_position(position: Vec2) -> Self {
+        // stretched to regular hexes, the tile is the one with the nearest center, and a point
+        // between two columns is always nearest to a tile in one of them
+        let column = (position.x - HEX_WIDTH / 2.0) / COLUMN_SPACING;
+        let first_column = column.floor() as i32;
+        (first_column..=first_column + 1)
+            .map(|x| {
+                let row = (position.y - HEX_HEIGHT / 2.0 - column_shift(x)) / HEX_HEIGHT;
+                let y = row.round();
+                let (dx, dy) = (column - x as f32, row - y);
+                // a regular flat-topped hex is 1.5 sizes from its neighbor column and √3 sizes from
+                // the next row
+                let distance = 2.25 * dx * dx + 3.0 * dy * dy;
+                (HexCoord::new(x, y as i32), distance)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap()
+            .0
+    }
+}
+
+impl AxialCoord {
+    pub fn to_cube(self) -> CubeCoord {
+        CubeCoord {
+            q: self.q,
+            r: self.r,
+            s: -self.q - self.r,
+        }
+    }
+}
+
+impl CubeCoord {
+    pub fn to_axial(self) -> AxialCoord {
+        AxialCoord { q: self.q, r: self.r }
+    }
+
+    pub fn distance(self, other: CubeCoord) -> i32 {
+        ((self.q - other.q).abs() + (self.r - other.r).abs() + (self.s - other.s).abs()) / 2
+    }
+
+    /// The hex containing a fractional axial position
+    fn round(q: f32, r: f32) -> CubeCoord {
+        let s = -q - r;
+        let (mut rounded_q, mut rounded_r, rounded_s) = (q.round(), r.round(), s.round());
+        let (q_diff, r_diff, s_diff) = ((rounded_q - q).abs(), (rounded_r - r).abs(), (rounded_s - s).abs());
+        // fix up whichever component was rounded the most, so the components still sum to zero
+        if q_diff > r_diff && q_diff > s_diff {
+            rounded_q = -rounded_r - rounded_s;
+        } else if r_diff > s_diff {
+            rounded_r = -rounded_q - rounded_s;
+        }
+        let (q, r) = (rounded_q as i32, rounded_r as i32);
+        CubeCoord { q, r, s: -q - r }
+    }
+}
+
+impl Add for CubeCoord {
+    type Output = CubeCoord;
+
+    fn add(self, other: CubeCoord) -> CubeCoord {
+        CubeCoord {
+            q: self.q + other.q,
+            r: self.r + other.r,
+            s: self.s + other.s,
+        }
+    }
+}
+
+impl Mul<i32> for CubeCoord {
+    type Output = CubeCoord;
+
+    fn mul(self, scale: i32) -> CubeCoord {
+        CubeCoord {
+            q: self.q * scale,
+            r: self.r * scale,
+            s: self.s * scale,
+        }
+    }
+}
+
+/// The extent of the map, and whether it wraps around east to west like a cylinder.
+///
+/// The bounded versions of the `HexCoord` queries only return tiles on the map, with columns
+/// wrapped around the date line when the map wraps.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MapBounds {
+    pub width: i32,
+    pub height: i32,
+    pub wrap_horizontal: bool,
+}
+
+impl MapBounds {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+
+    /// Brings a column back onto the map if the map wraps
+    pub fn wrap_x(&self, x: i32) -> i32 {
+        if self.wrap_horizontal {
+            x.rem_euclid(self.width)
+        } else {
+            x
+        }
+    }
+
+    /// The tile on the map at a coordinate, after wrapping, if there is one
+    pub fn wrap(&self, coord: HexCoord) -> Option<HexCoord> {
+        let wrapped = HexCoord::new(self.wrap_x(coord.x), coord.y);
+        if self.contains(wrapped.x, wrapped.y) {
+            Some(wrapped)
+        } else {
+            None
+        }
+    }
+
+    /// The tile drawn at a position in the local space of the tilemap, including on the
+    /// wrapped copies of the map either side of it
+    pub fn tile_at(&self, position: Vec2) -> Option<HexCoord> {
+        self.wrap(HexCoord::from_world_position(position))
+    }
+
+    /// The neighboring tile in a direction, if it is on the map
+    pub fn neighbor(&self, x: i32, y: i32, direction: HexDirection) -> Option<(i32, i32)> {
+        self.wrap(HexCoord::new(x, y).neighbor(direction)).map(|coord| (coord.x, coord.y))
+    }
+
+    /// Neighbors of a tile that are on the map
+    pub fn neighbors(&self, coord: HexCoord) -> impl Iterator<Item = HexCoord> {
+        let bounds = *self;
+        coord.neighbors().filter_map(move |neighbor| bounds.wrap(neighbor))
+    }
+
+    /// Number of steps between two tiles, going around the date line when that is shorter
+    pub fn distance(&self, a: HexCoord, b: HexCoord) -> i32 {
+        a.distance(self.nearest_copy(a, b))
+    }
+
+    /// Tiles on the map exactly `radius` steps away.
+    /// On wrapping maps narrower than the ring, tiles can appear more than once.
+    pub fn ring(&self, center: HexCoord, radius: i32) -> impl Iterator<Item = HexCoord> {
+        let bounds = *self;
+        center.ring(radius).filter_map(move |coord| bounds.wrap(coord))
+    }
+
+    /// Tiles on the map within `radius` steps, spiralling outwards
+    pub fn spiral(&self, center: HexCoord, radius: i32) -> impl Iterator<Item = HexCoord> {
+        let bounds = *self;
+        center.spiral(radius).filter_map(move |coord| bounds.wrap(coord))
+    }
+
+    /// Tiles on the map within `radius` steps
+    pub fn range(&self, center: HexCoord, radius: i32) -> impl Iterator<Item = HexCoord> {
+        let bounds = *self;
+        center.range(radius).filter_map(move |coord| bounds.wrap(coord))
+    }
+
+    /// Tiles on the shortest line between two tiles, going around the date line when that is shorter
+    pub fn line(&self, a: HexCoord, b: HexCoord) -> Vec<HexCoord> {
+        a.line_to(self.nearest_copy(a, b))
+            .into_iter()
+            .filter_map(|coord| self.wrap(coord))
+            .collect()
+    }
+
+    /// Whichever copy of `b` is closest to `a`, shifted a whole map width east or west on wrapping maps
+    pub fn nearest_copy(&self, a: HexCoord, b: HexCoord) -> HexCoord {
+        if !self.wrap_horizontal {
+            return b;
+        }
+        // the map width is a whole number of chunks, so shifting by it keeps column parity
+        [-self.width, 0, self.width]
+            .iter()
+            .map(|shift| HexCoord::new(b.x + shift, b.y))
+            .min_by_key(|copy| a.distance(*copy))
+            .unwrap()
+    }
+}
+
+/// How far the mesher moves tiles in column `x` up or down
+fn column_shift(x: i32) -> f32 {
+    if x % 2 == 0 { -COLUMN_SHIFT } else { COLUMN_SHIFT }
+}
+
+impl From<HexCoord> for MultiCoord {
+    /// Coordinate in the world's `HexGrid`, which is flat-topped with even parity to match
+    /// the tilemap's `ColumnEven` layout
+    fn from(coord: HexCoord) -> Self {
+        MultiCoord::from(Offset { row: coord.y, col: coord.x })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use chickenwire::{coordinate::CoordSys, hexgrid::{Parity, Tilt}, prelude::HexGrid};
+
+    fn coords() -> impl Iterator<Item = HexCoord> {
+        (-5..6).flat_map(|x| (-5..6).map(move |y| HexCoord::new(x, y)))
+    }
+
+    #[test]
+    fn axial_and_cube_round_trip() {
+        for coord in coords() {
+            assert_eq!(HexCoord::from_axial(coord.to_axial()), coord);
+            assert_eq!(HexCoord::from_cube(coord.to_cube()), coord);
+            let cube = coord.to_cube();
+            assert_eq!(cube.q + cube.r + cube.s, 0);
+        }
+    }
+
+    #[test]
+    fn neighbors_are_one_step_apart() {
+        for coord in coords() {
+            for &direction in HexDirection::ALL.iter() {
+                let neighbor = coord.neighbor(direction);
+                assert_eq!(coord.distance(neighbor), 1, "{:?} {:?}", coord, direction);
+                assert_eq!(neighbor.neighbor(direction.opposite()), coord);
+                assert_eq!(neighbor.to_cube(), coord.to_cube() + direction.cube());
+            }
+        }
+    }
+
+    #[test]
+    fn distance() {
+        assert_eq!(HexCoord::new(0, 0).distance(HexCoord::new(4, 0)), 4);
+        assert_eq!(HexCoord::new(0, 0).distance(HexCoord::new(0, 7)), 7);
+        // moving north-east climbs a row every two columns, starting from odd columns
+        assert_eq!(HexCoord::new(0, 0).distance(HexCoord::new(4, 2)), 4);
+        assert_eq!(HexCoord::new(1, 0).distance(HexCoord::new(3, 2)), 3);
+        assert_eq!(HexCoord::new(0, 0).distance(HexCoord::new(2, -1)), 2);
+        assert_eq!(HexCoord::new(1, 0).distance(HexCoord::new(5, -2)), 4);
+    }
+
+    #[test]
+    fn world_position_round_trip() {
+        for coord in coords() {
+            let center = coord.to_world_position();
+            assert_eq!(HexCoord::from_world_position(center), coord);
+            // points well inside the hex belong to it as well
+            for &(dx, dy) in [(6.0, 0.0), (-6.0, 0.0), (0.0, 12.0), (0.0, -12.0), (5.0, 8.0)].iter() {
+                assert_eq!(HexCoord::from_world_position(center + Vec2::new(dx, dy)), coord);
+            }
+        }
+    }
+
+    /// Center of a tile's quad as `HexChunkMesher` places it for `HexType::ColumnEven`: the quad
+    /// starts at the tile position times the tile size, moves left a quarter of a tile for every
+    /// column, then even columns move down and odd columns up by a quarter of a tile
+    fn mesher_tile_center(x: i32, y: i32) -> Vec2 {
+        let mut corner = Vec2::new(x as f32 * HEX_WIDTH, y as f32 * HEX_HEIGHT);
+        corner.x -= x as f32 * (0.25 * HEX_WIDTH).ceil();
+        let offset = (0.25 * HEX_HEIGHT).floor();
+        if x % 2 == 0 {
+            corner.y -= offset;
+        } else {
+            corner.y += offset;
+        }
+        corner + Vec2::new(HEX_WIDTH, HEX_HEIGHT) / 2.0
+    }
+
+    #[test]
+    fn world_positions_match_the_tilemap_mesher() {
+        assert_eq!(HexCoord::new(0, 0).to_world_position(), Vec2::new(16.0, 8.0));
+        assert_eq!(HexCoord::new(1, 0).to_world_position(), Vec2::new(40.0, 24.0));
+        assert_eq!(HexCoord::new(2, 3).to_world_position(), Vec2::new(64.0, 104.0));
+        for x in 0..12 {
+            for y in 0..12 {
+                let coord = HexCoord::new(x, y);
+                let center = mesher_tile_center(x, y);
+                assert_eq!(coord.to_world_position(), center);
+                // points inside the drawn hex belong to its tile
+                for &(dx, dy) in [(0.0, 0.0), (12.0, 0.0), (-12.0, 0.0), (0.0, 14.0), (0.0, -14.0), (10.0, 10.0), (-10.0, -10.0)].iter() {
+                    assert_eq!(HexCoord::from_world_position(center + Vec2::new(dx, dy)), coord);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn picks_tiles_where_the_mesher_draws_them() {
+        let bounds = MapBounds { width: 8, height: 6, wrap_horizontal: true };
+        // the wrapped copy is the whole map moved by its width in pixels
+        let map_width = bounds.width as f32 * COLUMN_SPACING;
+        for x in 0..bounds.width {
+            for y in 0..bounds.height {
+                let coord = HexCoord::new(x, y);
+                for &copy in [0.0, -map_width, map_width].iter() {
+                    let center = mesher_tile_center(x, y) + Vec2::new(copy, 0.0);
+                    for &(dx, dy) in [(0.0, 0.0), (12.0, 0.0), (-12.0, 0.0), (0.0, 14.0), (0.0, -14.0), (10.0, -10.0)].iter() {
+                        assert_eq!(bounds.tile_at(center + Vec2::new(dx, dy)), Some(coord));
+                    }
+                }
+            }
+        }
+        // below the bottom row of the even columns
+        assert_eq!(bounds.tile_at(mesher_tile_center(0, 0) - Vec2::new(0.0, HEX_HEIGHT)), None);
+        let bounded = MapBounds { wrap_horizontal: false, ..bounds };
+        assert_eq!(bounded.tile_at(mesher_tile_center(0, 0) - Vec2::new(map_width, 0.0)), None);
+    }
+
+    #[test]
+    fn neighbors_are_where_they_are_drawn() {
+        for coord in coords() {
+            for &direction in HexDirection::ALL.iter() {
+                let step = coord.neighbor(direction).to_world_position() - coord.to_world_position();
+                let expected = match direction {
+                    HexDirection::North => Vec2::new(0.0, HEX_HEIGHT),
+                    HexDirection::NorthEast => Vec2::new(COLUMN_SPACING, HEX_HEIGHT / 2.0),
+                    HexDirection::SouthEast => Vec2::new(COLUMN_SPACING, -HEX_HEIGHT / 2.0),
+                    HexDirection::South => Vec2::new(0.0, -HEX_HEIGHT),
+                    HexDirection::SouthWest => Vec2::new(-COLUMN_SPACING, -HEX_HEIGHT / 2.0),
+                    HexDirection::NorthWest => Vec2::new(-COLUMN_SPACING, HEX_HEIGHT / 2.0),
+                };
+                assert_eq!(step, expected, "{:?} {:?}", coord, direction);
+            }
+        }
+    }
+
+    #[test]
+    fn grid_coordinates_are_distinct() {
+        let mut hex_grid = HexGrid::<HexCoord>::new(Tilt::Flat, Parity::Even, CoordSys::Offset);
+        for x in 0..8 {
+            for y in 0..4 {
+                hex_grid.add(HexCoord::new(x, y).into(), HexCoord::new(x, y)).unwrap();
+            }
+        }
+        for x in 0..8 {
+            for y in 0..4 {
+                assert_eq!(*hex_grid.get(HexCoord::new(x, y).into()).unwrap(), HexCoord::new(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn rings() {
+        let center = HexCoord::new(3, -2);
+        assert_eq!(center.ring(0).collect::<Vec<_>>(), vec![center]);
+        for radius in 1..5 {
+            let ring: Vec<_> = center.ring(radius).collect();
+            assert_eq!(ring.len(), 6 * radius as usize);
+            assert_eq!(ring.iter().collect::<HashSet<_>>().len(), ring.len());
+            assert!(ring.iter().all(|tile| center.distance(*tile) == radius));
+            // each tile follows on from the one before, all the way around
+            for (index, tile) in ring.iter().enumerate() {
+                assert_eq!(tile.distance(ring[(index + 1) % ring.len()]), 1);
+            }
+        }
+        let neighbors: HashSet<_> = center.neighbors().collect();
+        assert_eq!(center.ring(1).collect::<HashSet<_>>(), neighbors);
+    }
+
+    #[test]
+    fn spirals_and_ranges() {
+        let center = HexCoord::new(-4, 1);
+        for radius in 0..5 {
+            let spiral: Vec<_> = center.spiral(radius).collect();
+            let expected_count = (3 * radius * (radius + 1) + 1) as usize;
+            assert_eq!(spiral.len(), expected_count);
+            assert_eq!(spiral[0], center);
+            assert!(spiral.windows(2).all(|pair| center.distance(pair[0]) <= center.distance(pair[1])));
+            let range: HashSet<_> = center.range(radius).collect();
+            assert_eq!(range.len(), expected_count);
+            assert_eq!(spiral.into_iter().collect::<HashSet<_>>(), range);
+        }
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(HexCoord::new(2, 2).line_to(HexCoord::new(2, 2)), vec![HexCoord::new(2, 2)]);
+        assert_eq!(
+            HexCoord::new(0, 0).line_to(HexCoord::new(0, 3)),
+            vec![HexCoord::new(0, 0), HexCoord::new(0, 1), HexCoord::new(0, 2), HexCoord::new(0, 3)],
+        );
+        for start in coords().step_by(7) {
+            for end in coords().step_by(5) {
+                let line = start.line_to(end);
+                assert_eq!(line.len(), start.distance(end) as usize + 1);
+                assert_eq!((line[0], *line.last().unwrap()), (start, end));
+                assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+            }
+        }
+    }
+
+    #[test]
+    fn bounded_neighbors() {
+        let bounds = MapBounds { width: 8, height: 4, wrap_horizontal: false };
+        let corner: HashSet<_> = bounds.neighbors(HexCoord::new(0, 0)).collect();
+        // the lower of the two columns, so nothing to the south-east
+        let expected: HashSet<_> = vec![HexCoord::new(0, 1), HexCoord::new(1, 0)].into_iter().collect();
+        assert_eq!(corner, expected);
+        assert_eq!(bounds.neighbors(HexCoord::new(3, 2)).count(), 6);
+        assert_eq!(bounds.range(HexCoord::new(0, 0), 2).count(), 7);
+        assert_eq!(bounds.distance(HexCoord::new(0, 0), HexCoord::new(7, 0)), 7);
+    }
+
+    #[test]
+    fn wrapped_neighbors() {
+        let bounds = MapBounds { width: 8, height: 4, wrap_horizontal: true };
+        let west_edge: HashSet<_> = bounds.neighbors(HexCoord::new(0, 1)).collect();
+        assert_eq!(west_edge.len(), 6);
+        assert!(west_edge.contains(&HexCoord::new(7, 1)));
+        assert!(west_edge.contains(&HexCoord::new(7, 0)));
+        assert_eq!(bounds.neighbor(7, 1, HexDirection::SouthEast), Some((0, 1)));
+        assert_eq!(bounds.neighbor(7, 1, HexDirection::NorthEast), Some((0, 2)));
+        // rows still end at the poles
+        assert_eq!(bounds.neighbors(HexCoord::new(2, 0)).count(), 3);
+        assert_eq!(bounds.distance(HexCoord::new(0, 0), HexCoord::new(7, 0)), 1);
+        assert_eq!(bounds.ring(HexCoord::new(0, 1), 1).count(), 6);
+    }
+
+    #[test]
+    fn wrapped_lines_cross_the_date_line() {
+        let bounds = MapBounds { width: 8, height: 4, wrap_horizontal: true };
+        let line = bounds.line(HexCoord::new(1, 1), HexCoord::new(6, 1));
+        assert_eq!(line.len(), 4);
+        assert_eq!((line[0], line[3]), (HexCoord::new(1, 1), HexCoord::new(6, 1)));
+        assert!(line.iter().all(|tile| bounds.contains(tile.x, tile.y)));
+        assert!(line.iter().any(|tile| tile.x == 7));
+    }
+}
@@ -1,32 +1,63 @@
+pub mod calendar;
+pub mod hex;
+#[cfg(feature = "client")]
 mod loading;
+#[cfg(feature = "client")]
 mod viewport;
+#[cfg(feature = "client")]
 mod mapview;
+#[cfg(feature = "client")]
 mod menu;
-mod playstate;
+#[cfg(feature = "client")]
+mod newgame;
+pub mod pathfinding;
+#[cfg(feature = "client")]
+pub mod picking;
+#[cfg(feature = "client")]
+pub mod playstate;
+#[cfg(feature = "client")]
+pub mod selection;
+#[cfg(feature = "client")]
+mod tileinfo;
+pub mod worldgen;
 
-use crate::viewport::ViewportPlugin;
-use crate::mapview::MapviewPlugin;
-use crate::loading::LoadingPlugin;
-use crate::menu::MenuPlugin;
-use crate::playstate::PlayStatePlugin;
-
-use bevy::app::AppBuilder;
+#[cfg(feature = "client")]
+use crate::{
+    loading::LoadingPlugin,
+    mapview::MapviewPlugin,
+    menu::MenuPlugin,
+    newgame::NewGamePlugin,
+    picking::PickingPlugin,
+    playstate::PlayStatePlugin,
+    selection::SelectionPlugin,
+    tileinfo::TileInfoPlugin,
+    viewport::ViewportPlugin,
+    worldgen::WorldGenPlugin,
+};
+#[cfg(feature = "client")]
+use bevy::{app::AppBuilder, prelude::*};
 // use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
-use bevy::prelude::*;
+#[cfg(feature = "client")]
 use bevy_ecs_tilemap::TileMapPlugin;
+#[cfg(feature = "client")]
 use bevy_inspector_egui::{InspectorPlugin, widgets::InspectorQuery};
 
+#[cfg(feature = "client")]
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 enum GameState {
     Loading,
     Playing,
     Menu,
+    NewGame,
 }
 
+#[cfg(feature = "client")]
 struct Inspected;
 
+#[cfg(feature = "client")]
 pub struct GamePlugin;
 
+#[cfg(feature = "client")]
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app
@@ -35,10 +66,15 @@ impl Plugin for GamePlugin {
             .add_state(GameState::Loading)
             .add_plugin(LoadingPlugin)
             .add_plugin(MenuPlugin)
+            .add_plugin(NewGamePlugin)
 
             .add_plugin(PlayStatePlugin)
             .add_plugin(ViewportPlugin)
+            .add_plugin(WorldGenPlugin)
             .add_plugin(MapviewPlugin)
+            .add_plugin(PickingPlugin)
+            .add_plugin(SelectionPlugin)
+            .add_plugin(TileInfoPlugin)
             // .add_plugin(FrameTimeDiagnosticsPlugin::default())
             // .add_plugin(LogDiagnosticsPlugin::default())
             ;
@@ -1,8 +1,6 @@
-use crate::{GameState, loading::TextureAssets};
+use crate::{GameState, hex::{COLUMN_SPACING, HEX_HEIGHT, HEX_WIDTH, HexCoord}, loading::TextureAssets, viewport::ViewportCamera, worldgen::{CHUNK_SIZE_HEIGHT, CHUNK_SIZE_WIDTH, GenerateWorldEvent, HexTile, WorldData, terrain::{FOREST_TILESET_INDEX, TerrainType}}};
 use bevy::{prelude::*};
 use bevy_ecs_tilemap::prelude::*;
-use chickenwire::{coordinate::{CoordSys, MultiCoord, Offset}, hexgrid::{Parity, Tilt}, prelude::HexGrid};
-use noise::{*, utils::{*}};
 
 pub struct MapviewPlugin;
 
@@ -10,99 +8,106 @@ impl Plugin for MapviewPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_system_set(
             SystemSet::on_enter(GameState::Playing)
+                .with_system(request_world.system())
+        );
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
                 .with_system(
                     setup_tilemap.system()
                         .label("setup_tilemap")
                 )
+                .with_system(position_wrapped_map.system())
         );
     }
 }
 
-const TILE_WIDTH: f32 = 32.0;
-const TILE_HEIGHT: f32 = 32.0;
+/// Second copy of the tilemap drawn next to the real one on maps that wrap east to west,
+/// so there is no gap when the camera looks across the date line
+struct WrappedMapCopy;
 
-#[derive(Debug, Copy, Clone)]
-enum TerrainType {
-    OCEAN = 0,
-    LAND = 2,
+/// Width of the whole map in world units, which is how far the camera moves to go around the world
+pub fn map_pixel_width(world: &WorldData) -> f32 {
+    world.width as f32 * COLUMN_SPACING
 }
 
-#[derive(Debug, Copy, Clone)]
-struct HexTile {
-    terrain_type: TerrainType,
+impl From<HexCoord> for MapVec2 {
+    fn from(coord: HexCoord) -> Self {
+        MapVec2::new(coord.x, coord.y)
+    }
 }
 
-fn generate_heightmap(width: usize, height: usize) -> NoiseMap {
-    let noise = Fbm::new()
-        .set_seed(1234)
-        .set_persistence(0.5)
-        .set_frequency(1.0)
-        .set_lacunarity(2.0);
-    let builder = SphereMapBuilder::new(&noise);
-    let noise_map = builder
-        .set_bounds(-90., 90., -180., 180.)
-        .set_size(width, height)
-        .build();
-    noise_map.write_to_file("noise_test.png");
-    return noise_map;
+impl From<MapVec2> for HexCoord {
+    fn from(pos: MapVec2) -> Self {
+        HexCoord::new(pos.x, pos.y)
+    }
 }
 
-const CHUNK_WIDTH: f32 = 6.0;
-const CHUNK_HEIGHT: f32 = 3.0;
-const CHUNK_SIZE_WIDTH: f32 = 64.0;
-const CHUNK_SIZE_HEIGHT: f32 = 64.0;
-const MAP_WIDTH: i32 = (CHUNK_WIDTH * CHUNK_SIZE_WIDTH) as i32;
-const MAP_HEIGHT: i32 = (CHUNK_HEIGHT * CHUNK_SIZE_HEIGHT) as i32;
+/// Index of the tile's texture in `textures/tileset.png`
+fn tile_texture_index(hex_tile: &HexTile) -> u32 {
+    match hex_tile.terrain_type {
+        TerrainType::Plains | TerrainType::Hills if hex_tile.biome.is_forest() => FOREST_TILESET_INDEX,
+        terrain_type => terrain_type.tileset_index(),
+    }
+}
 
+fn request_world(mut events: EventWriter<GenerateWorldEvent>) {
+    events.send(GenerateWorldEvent);
+}
+
+/// Builds the tilemap once the world has been generated
 fn setup_tilemap(
   mut commands: Commands,
   texture_assets: Res<TextureAssets>,
   textures: Res<Assets<Texture>>,
   mut meshes: ResMut<Assets<Mesh>>,
   mut materials: ResMut<Assets<ColorMaterial>>,
+  world: Option<Res<WorldData>>,
 ) {
+    let world = match world {
+        Some(world) if world.is_added() => world,
+        _ => return,
+    };
     println!("Setup game map");
 
     let texture = textures.get(&texture_assets.texture_tileset).unwrap();
     println!("Tileset loaded: {} {}", texture.size.width, texture.size.height);
     let asset = ColorMaterial::texture(texture_assets.texture_tileset.clone());
     let material_handle = materials.add(asset);
+    let texture_size = Vec2::new(texture.size.width as f32, texture.size.height as f32);
 
-    let heightmap = generate_heightmap(MAP_WIDTH as usize, MAP_HEIGHT as usize);
-    // println!("(0,0) = {}", heightmap.get_value(0, 0));
-
-    let mut hex_grid = HexGrid::<HexTile>::new(Tilt::Flat, Parity::Even, CoordSys::Offset);
-    for x in 0..MAP_WIDTH {
-        for y in 0..MAP_HEIGHT {
-            let height = heightmap.get_value(x as usize, y as usize);
-            // println!("height at {},{} = {:.}", x as usize, y as usize, height);
-            let mut terrain_type = TerrainType::OCEAN;
-            if height >= 0.05 {
-                terrain_type = TerrainType::LAND;
-            }
-            hex_grid.add(MultiCoord::from(Offset { row: x, col: y }), HexTile {
-                terrain_type,
-            }).unwrap();
-        }
+    spawn_map(&mut commands, &mut meshes, material_handle.clone(), texture_size, &world);
+    if world.settings.wrap_horizontal {
+        let map_entity = spawn_map(&mut commands, &mut meshes, material_handle, texture_size, &world);
+        commands.entity(map_entity).insert(WrappedMapCopy);
     }
+}
+
+fn spawn_map(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    material_handle: Handle<ColorMaterial>,
+    texture_size: Vec2,
+    world: &WorldData,
+) -> Entity {
+    let map_width = world.width;
+    let map_height = world.height;
 
     let mut map = Map::new(
-        Vec2::new(CHUNK_WIDTH, CHUNK_HEIGHT).into(), // size in chunks
-        Vec2::new(CHUNK_SIZE_WIDTH, CHUNK_SIZE_HEIGHT).into(), 
-        Vec2::new(TILE_WIDTH, TILE_HEIGHT), 
-        Vec2::new(texture.size.width as f32, texture.size.height as f32), 
+        Vec2::new(world.settings.chunks_width as f32, world.settings.chunks_height as f32).into(), // size in chunks
+        Vec2::new(CHUNK_SIZE_WIDTH as f32, CHUNK_SIZE_HEIGHT as f32).into(), 
+        Vec2::new(HEX_WIDTH, HEX_HEIGHT), 
+        texture_size,
         0
     );
     map.mesher = Box::new(HexChunkMesher::new(HexType::ColumnEven));
     let map_entity = commands.spawn().id();
-    map.build(&mut commands, &mut meshes, material_handle.clone(), map_entity, false);
-    println!("Map width: {}, Map height: {} ({} tiles)", MAP_WIDTH, MAP_HEIGHT, MAP_WIDTH * MAP_HEIGHT);
-    for x in 0..MAP_WIDTH {
-        for y in 0..MAP_HEIGHT {
-            let hex_tile = hex_grid.get(MultiCoord::from(Offset { row: x, col: y })).unwrap();
-            let tile_pos = MapVec2::new(x, y);
-            map.add_tile(&mut commands, tile_pos, Tile {
-                texture_index: hex_tile.terrain_type as u32,
+    map.build(commands, meshes, material_handle, map_entity, false);
+    println!("Map width: {}, Map height: {} ({} tiles)", map_width, map_height, map_width * map_height);
+    for x in 0..map_width {
+        for y in 0..map_height {
+            let hex_tile = world.tile(x, y);
+            map.add_tile(commands, HexCoord::new(x, y).into(), Tile {
+                texture_index: tile_texture_index(hex_tile),
                 ..Default::default()
             }).unwrap();
         }
@@ -112,4 +117,36 @@ fn setup_tilemap(
         map,
         ..Default::default()
     });
+    map_entity
+}
+
+/// Keeps the copy of a wrapping map on whichever side of the real map the camera is looking at
+fn position_wrapped_map(
+    world: Option<Res<WorldData>>,
+    camera_query: Query<&Transform, With<ViewportCamera>>,
+    mut map_query: Query<&mut Transform, (With<WrappedMapCopy>, Without<ViewportCamera>)>,
+) {
+    let world = match world {
+        Some(world) => world,
+        None => return,
+    };
+    let map_width = map_pixel_width(&world);
+    for camera_transform in camera_query.iter() {
+        let side = if camera_transform.translation.x > map_width / 2.0 { 1.0 } else { -1.0 };
+        for mut transform in map_query.iter_mut() {
+            transform.translation.x = side * map_width;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_vec2_round_trip() {
+        let pos: MapVec2 = HexCoord::new(3, 7).into();
+        assert_eq!((pos.x, pos.y), (3, 7));
+        assert_eq!(HexCoord::from(pos), HexCoord::new(3, 7));
+    }
 }
@@ -6,16 +6,20 @@ pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_resource::<ButtonMaterials>()
+            .add_startup_system(setup_ui_camera.system())
             .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(setup_menu.system()))
             .add_system_set(
-                SystemSet::on_update(GameState::Menu).with_system(click_play_button.system()),
+                SystemSet::on_update(GameState::Menu)
+                    .with_system(click_play_button.system())
+                    .with_system(highlight_buttons.system()),
             );
     }
 }
 
-struct ButtonMaterials {
-    normal: Handle<ColorMaterial>,
-    hovered: Handle<ColorMaterial>,
+/// Materials of the menu buttons, shared by the menu screens
+pub struct ButtonMaterials {
+    pub normal: Handle<ColorMaterial>,
+    pub hovered: Handle<ColorMaterial>,
 }
 
 impl FromWorld for ButtonMaterials {
@@ -30,12 +34,15 @@ impl FromWorld for ButtonMaterials {
 
 struct PlayButton;
 
+fn setup_ui_camera(mut commands: Commands) {
+    commands.spawn_bundle(UiCameraBundle::default());
+}
+
 fn setup_menu(
     mut commands: Commands,
     fonts: Res<FontAssets>,
     button_materials: Res<ButtonMaterials>,
 ) {
-    commands.spawn_bundle(UiCameraBundle::default());
     commands
         .spawn_bundle(ButtonBundle {
             style: Style {
@@ -70,25 +77,33 @@ fn setup_menu(
 type ButtonInteraction<'a> = (
     Entity,
     &'a Interaction,
-    &'a mut Handle<ColorMaterial>,
     &'a Children,
 );
 
 fn click_play_button(
     mut commands: Commands,
-    button_materials: Res<ButtonMaterials>,
     mut state: ResMut<State<GameState>>,
-    mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<Button>)>,
+    interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<Button>)>,
     text_query: Query<Entity, With<Text>>,
 ) {
-    for (button, interaction, mut material, children) in interaction_query.iter_mut() {
+    for (button, interaction, children) in interaction_query.iter() {
         let text = text_query.get(children[0]).unwrap();
+        if *interaction == Interaction::Clicked {
+            commands.entity(button).despawn();
+            commands.entity(text).despawn();
+            state.set(GameState::NewGame).unwrap();
+        }
+    }
+}
+
+/// Highlights the button under the mouse
+pub fn highlight_buttons(
+    button_materials: Res<ButtonMaterials>,
+    mut interaction_query: Query<(&Interaction, &mut Handle<ColorMaterial>), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, mut material) in interaction_query.iter_mut() {
         match *interaction {
-            Interaction::Clicked => {
-                commands.entity(button).despawn();
-                commands.entity(text).despawn();
-                state.set(GameState::Playing).unwrap();
-            }
+            Interaction::Clicked => {}
             Interaction::Hovered => {
                 *material = button_materials.hovered.clone();
             }
@@ -0,0 +1,277 @@
+use crate::{GameState, loading::FontAssets, menu::{ButtonMaterials, highlight_buttons}, worldgen::{WorldGenSettings, presets::{MAP_SIZES, SeedEntry, map_size_index, step_land_percentage, step_map_size, step_sea_level}}};
+use bevy::{prelude::*, window::ReceivedCharacter};
+
+pub struct NewGamePlugin;
+
+impl Plugin for NewGamePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<SeedEntry>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::NewGame).with_system(setup_new_game.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::NewGame)
+                    .with_system(new_game_buttons.system())
+                    .with_system(highlight_buttons.system())
+                    .with_system(seed_input.system())
+                    .with_system(settings_text_update.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::NewGame).with_system(cleanup_new_game.system()),
+            );
+    }
+}
+
+struct NewGameScreen;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum NewGameButton {
+    RandomizeSeed,
+    MapSizeDown,
+    MapSizeUp,
+    SeaLevelDown,
+    SeaLevelUp,
+    LandPercentageDown,
+    LandPercentageUp,
+    ToggleWrap,
+    Back,
+    Start,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum SettingText {
+    Seed,
+    MapSize,
+    SeaLevel,
+    LandPercentage,
+    Wrap,
+}
+
+fn setting_value(setting: SettingText, settings: &WorldGenSettings) -> String {
+    match setting {
+        SettingText::Seed => format!("{}", settings.seed),
+        SettingText::MapSize => {
+            let name = match map_size_index(settings) {
+                Some(index) => MAP_SIZES[index].0,
+                None => "Custom",
+            };
+            format!("{} ({}x{})", name, settings.map_width(), settings.map_height())
+        }
+        SettingText::SeaLevel => match settings.land_percentage {
+            Some(_) => "From land %".to_string(),
+            None => format!("{:.2}", settings.sea_level),
+        },
+        SettingText::LandPercentage => match settings.land_percentage {
+            Some(land_percentage) => format!("{:.0}%", land_percentage * 100.0),
+            None => "Off".to_string(),
+        },
+        SettingText::Wrap => if settings.wrap_horizontal { "On" } else { "Off" }.to_string(),
+    }
+}
+
+fn setup_new_game(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    button_materials: Res<ButtonMaterials>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    settings: Res<WorldGenSettings>,
+    mut seed_entry: ResMut<SeedEntry>,
+) {
+    seed_entry.replace_next();
+    let background = materials.add(Color::NONE.into());
+    let text_style = TextStyle {
+        font: fonts.fira_sans.clone(),
+        font_size: 28.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: background.clone(),
+            ..Default::default()
+        })
+        .insert(NewGameScreen)
+        .with_children(|parent| {
+            spawn_setting_row(parent, &button_materials, &background, &text_style, &settings, "Seed", SettingText::Seed,
+                &[(NewGameButton::RandomizeSeed, "Randomize")]);
+            spawn_setting_row(parent, &button_materials, &background, &text_style, &settings, "Map size", SettingText::MapSize,
+                &[(NewGameButton::MapSizeDown, "-"), (NewGameButton::MapSizeUp, "+")]);
+            spawn_setting_row(parent, &button_materials, &background, &text_style, &settings, "Sea level", SettingText::SeaLevel,
+                &[(NewGameButton::SeaLevelDown, "-"), (NewGameButton::SeaLevelUp, "+")]);
+            spawn_setting_row(parent, &button_materials, &background, &text_style, &settings, "Land", SettingText::LandPercentage,
+                &[(NewGameButton::LandPercentageDown, "-"), (NewGameButton::LandPercentageUp, "+")]);
+            spawn_setting_row(parent, &button_materials, &background, &text_style, &settings, "World wrap", SettingText::Wrap,
+                &[(NewGameButton::ToggleWrap, "Toggle")]);
+
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(16.0)),
+                        ..Default::default()
+                    },
+                    material: background.clone(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    spawn_button(parent, &button_materials, &text_style, NewGameButton::Back, "Back");
+                    spawn_button(parent, &button_materials, &text_style, NewGameButton::Start, "Start");
+                });
+        });
+}
+
+fn spawn_setting_row(
+    parent: &mut ChildBuilder,
+    button_materials: &ButtonMaterials,
+    background: &Handle<ColorMaterial>,
+    text_style: &TextStyle,
+    settings: &WorldGenSettings,
+    label: &str,
+    setting: SettingText,
+    buttons: &[(NewGameButton, &str)],
+) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: Rect::all(Val::Px(4.0)),
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: background.clone(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(360.0), Val::Auto),
+                        ..Default::default()
+                    },
+                    text: Text {
+                        sections: vec![
+                            TextSection {
+                                value: format!("{}: ", label),
+                                style: text_style.clone(),
+                            },
+                            TextSection {
+                                value: setting_value(setting, settings),
+                                style: text_style.clone(),
+                            },
+                        ],
+                        alignment: Default::default(),
+                    },
+                    ..Default::default()
+                })
+                .insert(setting);
+            for &(button, label) in buttons {
+                spawn_button(parent, button_materials, text_style, button, label);
+            }
+        });
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    button_materials: &ButtonMaterials,
+    text_style: &TextStyle,
+    button: NewGameButton,
+    label: &str,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                min_size: Size::new(Val::Px(40.0), Val::Px(36.0)),
+                padding: Rect::all(Val::Px(4.0)),
+                margin: Rect::all(Val::Px(4.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: button_materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(label, text_style.clone(), Default::default()),
+                ..Default::default()
+            });
+        });
+}
+
+fn new_game_buttons(
+    mut state: ResMut<State<GameState>>,
+    mut settings: ResMut<WorldGenSettings>,
+    mut seed_entry: ResMut<SeedEntry>,
+    interaction_query: Query<(&Interaction, &NewGameButton), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match *button {
+            NewGameButton::RandomizeSeed => {
+                settings.seed = rand::random();
+                seed_entry.replace_next();
+            }
+            NewGameButton::MapSizeDown => step_map_size(&mut settings, false),
+            NewGameButton::MapSizeUp => step_map_size(&mut settings, true),
+            NewGameButton::SeaLevelDown => step_sea_level(&mut settings, false),
+            NewGameButton::SeaLevelUp => step_sea_level(&mut settings, true),
+            NewGameButton::LandPercentageDown => step_land_percentage(&mut settings, false),
+            NewGameButton::LandPercentageUp => step_land_percentage(&mut settings, true),
+            NewGameButton::ToggleWrap => {
+                settings.wrap_horizontal = !settings.wrap_horizontal;
+            }
+            NewGameButton::Back => {
+                state.set(GameState::Menu).unwrap();
+            }
+            NewGameButton::Start => {
+                state.set(GameState::Playing).unwrap();
+            }
+        }
+    }
+}
+
+/// Lets the seed be typed in directly so specific worlds can be reproduced.
+/// Backspace removes the last digit and Delete clears the seed.
+fn seed_input(
+    mut char_events: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<WorldGenSettings>,
+    mut seed_entry: ResMut<SeedEntry>,
+) {
+    for event in char_events.iter() {
+        if let Some(digit) = event.char.to_digit(10) {
+            settings.seed = seed_entry.type_digit(settings.seed, digit);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        settings.seed = seed_entry.backspace(settings.seed);
+    }
+    if keyboard_input.just_pressed(KeyCode::Delete) {
+        settings.seed = seed_entry.clear();
+    }
+}
+
+fn settings_text_update(
+    settings: Res<WorldGenSettings>,
+    mut query: Query<(&mut Text, &SettingText)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (mut text, setting) in query.iter_mut() {
+        text.sections[1].value = setting_value(*setting, &settings);
+    }
+}
+
+fn cleanup_new_game(mut commands: Commands, query: Query<Entity, With<NewGameScreen>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
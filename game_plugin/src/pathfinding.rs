@@ -0,0 +1,329 @@
+//! Finding the cheapest route between two tiles for a unit.
+//!
+//! The map is turned into a `petgraph` graph with a node per tile and an edge to every
+//! neighbor the unit can enter, weighted by the cost of entering it. Paths are found with A*.
+
+pub mod navigation;
+
+use crate::{hex::{HexCoord, HexDirection, MapBounds}, worldgen::{WorldData, terrain::TerrainType}};
+use petgraph::{algo::{astar, dijkstra}, graph::{DiGraph, NodeIndex}, visit::{EdgeRef, NodeFiltered, Reversed}};
+use std::collections::HashMap;
+
+/// Extra cost for a land unit to cross a river. Rivers run along the edges between tiles,
+/// so walking along a river bank costs nothing extra.
+pub const RIVER_CROSSING_COST: u32 = 2;
+/// The cheapest any step can be, used to keep the A* estimate from overshooting
+const MIN_STEP_COST: u32 = 1;
+
+/// How a unit moves, deciding which tiles it can enter
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MovementType {
+    Land,
+    Naval,
+}
+
+impl MovementType {
+    /// Cost to enter a tile of this terrain, or `None` if the unit can't go there
+    pub fn terrain_cost(self, terrain_type: TerrainType) -> Option<u32> {
+        match self {
+            MovementType::Land => match terrain_type {
+                TerrainType::Beach | TerrainType::Plains => Some(1),
+                TerrainType::Hills => Some(2),
+                TerrainType::Mountains => Some(3),
+                TerrainType::SnowPeaks | TerrainType::DeepOcean | TerrainType::Coast | TerrainType::Lake => None,
+            },
+            MovementType::Naval => match terrain_type {
+                TerrainType::DeepOcean | TerrainType::Coast | TerrainType::Lake => Some(1),
+                _ => None,
+            },
+        }
+    }
+
+    /// Cost to step from a tile to its neighbor in a direction, or `None` if the unit can't
+    pub fn step_cost(self, world: &WorldData, from: HexCoord, direction: HexDirection, to: HexCoord) -> Option<u32> {
+        let cost = self.terrain_cost(world.tile(to.x, to.y).terrain_type)?;
+        let crosses_river = world.river_flow(from, direction) > 0;
+        if self == MovementType::Land && crosses_river {
+            Some(cost + RIVER_CROSSING_COST)
+        } else {
+            Some(cost)
+        }
+    }
+}
+
+/// A route between two tiles, including both ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub tiles: Vec<HexCoord>,
+    /// Sum of the cost of every step
+    pub cost: u32,
+}
+
+/// The tiles a unit can move between and what each step costs
+pub struct MovementGraph {
+    movement: MovementType,
+    bounds: MapBounds,
+    graph: DiGraph<HexCoord, u32>,
+    /// Node of each tile, in row-major order
+    nodes: Vec<NodeIndex>,
+}
+
+impl MovementGraph {
+    pub fn new(world: &WorldData, movement: MovementType) -> Self {
+        let bounds = world.bounds();
+        let mut graph = DiGraph::with_capacity((bounds.width * bounds.height) as usize, 0);
+        let mut nodes = Vec::with_capacity((bounds.width * bounds.height) as usize);
+        for y in 0..bounds.height {
+            for x in 0..bounds.width {
+                nodes.push(graph.add_node(HexCoord::new(x, y)));
+            }
+        }
+        let mut movement_graph = MovementGraph { movement, bounds, graph, nodes };
+        for y in 0..bounds.height {
+            for x in 0..bounds.width {
+                for &direction in HexDirection::ALL.iter() {
+                    let from = HexCoord::new(x, y);
+                    if let Some((nx, ny)) = bounds.neighbor(x, y, direction) {
+                        let to = HexCoord::new(nx, ny);
+                        if let Some(cost) = movement.step_cost(world, from, direction, to) {
+                            movement_graph.graph.add_edge(movement_graph.node(from), movement_graph.node(to), cost);
+                        }
+                    }
+                }
+            }
+        }
+        movement_graph
+    }
+
+    /// Recomputes the steps into and out of a tile after it changed in the world
+    pub fn update_tile(&mut self, world: &WorldData, coord: HexCoord) {
+        for &direction in HexDirection::ALL.iter() {
+            if let Some((nx, ny)) = self.bounds.neighbor(coord.x, coord.y, direction) {
+                let neighbor = HexCoord::new(nx, ny);
+                let outgoing = self.movement.step_cost(world, coord, direction, neighbor);
+                self.set_step_cost(coord, neighbor, outgoing);
+                let incoming = self.movement.step_cost(world, neighbor, direction.opposite(), coord);
+                self.set_step_cost(neighbor, coord, incoming);
+            }
+        }
+    }
+
+    fn set_step_cost(&mut self, from: HexCoord, to: HexCoord, cost: Option<u32>) {
+        let (from_node, to_node) = (self.node(from), self.node(to));
+        match cost {
+            Some(cost) => {
+                self.graph.update_edge(from_node, to_node, cost);
+            }
+            None => {
+                if let Some(edge) = self.graph.find_edge(from_node, to_node) {
+                    self.graph.remove_edge(edge);
+                }
+            }
+        }
+    }
+
+    /// Cost of stepping between two neighboring tiles, or `None` if the unit can't
+    pub fn step_cost(&self, from: HexCoord, to: HexCoord) -> Option<u32> {
+        self.graph
+            .find_edge(self.node(from), self.node(to))
+            .map(|edge| self.graph[edge])
+    }
+
+    pub fn bounds(&self) -> MapBounds {
+        self.bounds
+    }
+
+    pub fn movement(&self) -> MovementType {
+        self.movement
+    }
+
+    fn node(&self, coord: HexCoord) -> NodeIndex {
+        self.nodes[(coord.x + coord.y * self.bounds.width) as usize]
+    }
+
+    /// The cheapest path between two tiles, or `None` if the goal can't be reached
+    pub fn find_path(&self, start: HexCoord, goal: HexCoord) -> Option<Path> {
+        if !self.bounds.contains(start.x, start.y) || !self.bounds.contains(goal.x, goal.y) {
+            return None;
+        }
+        let goal_node = self.node(goal);
+        let (cost, nodes) = astar(
+            &self.graph,
+            self.node(start),
+            |node| node == goal_node,
+            |edge| *edge.weight(),
+            |node| self.bounds.distance(self.graph[node], goal) as u32 * MIN_STEP_COST,
+        )?;
+        Some(Path {
+            tiles: nodes.into_iter().map(|node| self.graph[node]).collect(),
+            cost,
+        })
+    }
+
+    /// The cheapest path between two tiles that only passes through tiles accepted by `filter`
+    pub fn find_path_within(&self, start: HexCoord, goal: HexCoord, filter: impl Fn(HexCoord) -> bool) -> Option<Path> {
+        if !self.bounds.contains(start.x, start.y) || !self.bounds.contains(goal.x, goal.y) {
+            return None;
+        }
+        let graph = NodeFiltered::from_fn(&self.graph, |node| filter(self.graph[node]));
+        let goal_node = self.node(goal);
+        let (cost, nodes) = astar(
+            &graph,
+            self.node(start),
+            |node| node == goal_node,
+            |edge| *edge.weight(),
+            |node| self.bounds.distance(self.graph[node], goal) as u32 * MIN_STEP_COST,
+        )?;
+        Some(Path {
+            tiles: nodes.into_iter().map(|node| self.graph[node]).collect(),
+            cost,
+        })
+    }
+
+    /// Cost of the cheapest path from a tile to every tile reachable through tiles accepted by `filter`
+    pub fn costs_within(&self, start: HexCoord, filter: impl Fn(HexCoord) -> bool) -> HashMap<HexCoord, u32> {
+        let graph = NodeFiltered::from_fn(&self.graph, |node| filter(self.graph[node]));
+        dijkstra(&graph, self.node(start), None, |edge| *edge.weight())
+            .into_iter()
+            .map(|(node, cost)| (self.graph[node], cost))
+            .collect()
+    }
+
+    /// Cost of the cheapest path to a tile from every tile that reaches it through tiles accepted by `filter`
+    pub fn costs_to_within(&self, goal: HexCoord, filter: impl Fn(HexCoord) -> bool) -> HashMap<HexCoord, u32> {
+        // searching backwards along the steps finds the costs to the goal rather than from it
+        let graph = NodeFiltered::from_fn(Reversed(&self.graph), |node| filter(self.graph[node]));
+        dijkstra(&graph, self.node(goal), None, |edge| *edge.weight())
+            .into_iter()
+            .map(|(node, cost)| (self.graph[node], cost))
+            .collect()
+    }
+}
+
+/// Finds the cheapest path between two tiles for a unit.
+/// Builds the movement graph from scratch, so keep a `MovementGraph` around when finding many paths.
+pub fn find_path(world: &WorldData, start: HexCoord, goal: HexCoord, movement: MovementType) -> Option<Path> {
+    MovementGraph::new(world, movement).find_path(start, goal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worldgen::WorldGenSettings;
+
+    /// A world of plains with no generation run, so tests can lay out terrain by hand
+    fn plains_world(wrap_horizontal: bool) -> WorldData {
+        let mut world = WorldData::new(WorldGenSettings {
+            chunks_width: 1,
+            chunks_height: 1,
+            wrap_horizontal,
+            ..Default::default()
+        });
+        for y in 0..world.height {
+            for x in 0..world.width {
+                world.tile_mut(x, y).terrain_type = TerrainType::Plains;
+            }
+        }
+        world
+    }
+
+    fn assert_connected(path: &Path, bounds: MapBounds) {
+        for pair in path.tiles.windows(2) {
+            assert_eq!(bounds.distance(pair[0], pair[1]), 1, "{:?}", path.tiles);
+        }
+    }
+
+    #[test]
+    fn straight_path_over_plains() {
+        let world = plains_world(false);
+        let path = find_path(&world, HexCoord::new(10, 10), HexCoord::new(10, 15), MovementType::Land).unwrap();
+        assert_eq!(path.cost, 5);
+        assert_eq!(path.tiles.len(), 6);
+        assert_eq!(path.tiles[0], HexCoord::new(10, 10));
+        assert_eq!(*path.tiles.last().unwrap(), HexCoord::new(10, 15));
+        assert_connected(&path, world.bounds());
+    }
+
+    #[test]
+    fn start_is_goal() {
+        let world = plains_world(false);
+        let path = find_path(&world, HexCoord::new(3, 3), HexCoord::new(3, 3), MovementType::Land).unwrap();
+        assert_eq!(path, Path { tiles: vec![HexCoord::new(3, 3)], cost: 0 });
+    }
+
+    #[test]
+    fn goes_around_mountains_when_cheaper() {
+        let mut world = plains_world(false);
+        // a short mountain wall across column 10
+        for y in 9..11 {
+            world.tile_mut(10, y).terrain_type = TerrainType::Mountains;
+        }
+        let path = find_path(&world, HexCoord::new(8, 10), HexCoord::new(12, 10), MovementType::Land).unwrap();
+        assert!(path.tiles.iter().all(|tile| world.tile(tile.x, tile.y).terrain_type == TerrainType::Plains));
+        assert_eq!(path.cost, 4);
+        assert_connected(&path, world.bounds());
+    }
+
+    #[test]
+    fn water_is_impassable_for_land_units() {
+        let mut world = plains_world(false);
+        for y in 0..world.height {
+            world.tile_mut(20, y).terrain_type = TerrainType::Coast;
+        }
+        assert_eq!(find_path(&world, HexCoord::new(15, 5), HexCoord::new(25, 5), MovementType::Land), None);
+        assert_eq!(find_path(&world, HexCoord::new(15, 5), HexCoord::new(20, 5), MovementType::Land), None);
+    }
+
+    #[test]
+    fn naval_units_stay_on_water() {
+        let mut world = plains_world(false);
+        for x in 5..20 {
+            world.tile_mut(x, 30).terrain_type = TerrainType::DeepOcean;
+        }
+        let path = find_path(&world, HexCoord::new(5, 30), HexCoord::new(19, 30), MovementType::Naval).unwrap();
+        assert!(path.tiles.iter().all(|tile| world.tile(tile.x, tile.y).terrain_type.is_water()));
+        assert_eq!(find_path(&world, HexCoord::new(5, 30), HexCoord::new(5, 31), MovementType::Naval), None);
+    }
+
+    #[test]
+    fn off_map_tiles_have_no_path() {
+        let world = plains_world(false);
+        let graph = MovementGraph::new(&world, MovementType::Land);
+        let (on_map, off_map) = (HexCoord::new(3, 3), HexCoord::new(-1, 3));
+        assert_eq!(graph.find_path(on_map, off_map), None);
+        assert_eq!(graph.find_path_within(off_map, on_map, |_| true), None);
+        assert_eq!(graph.find_path_within(on_map, HexCoord::new(3, world.height), |_| true), None);
+    }
+
+    #[test]
+    fn crossing_rivers_costs_extra() {
+        let mut world = plains_world(false);
+        // a river along the eastern edges of column 10, from row 5 to row 15
+        for y in 5..=15 {
+            let hex_tile = world.tile_mut(10, y);
+            hex_tile.rivers[HexDirection::NorthEast as usize] = 1;
+            hex_tile.rivers[HexDirection::SouthEast as usize] = 1;
+        }
+        let movement = MovementType::Land;
+        let (west, east) = (HexCoord::new(10, 10), HexCoord::new(11, 10));
+        assert_eq!(movement.step_cost(&world, west, HexDirection::NorthEast, east), Some(1 + RIVER_CROSSING_COST));
+        assert_eq!(movement.step_cost(&world, east, HexDirection::SouthWest, west), Some(1 + RIVER_CROSSING_COST));
+        assert_eq!(find_path(&world, west, east, movement).unwrap().cost, 1 + RIVER_CROSSING_COST);
+        // walking along the bank doesn't cross the river
+        assert_eq!(movement.step_cost(&world, west, HexDirection::North, HexCoord::new(10, 11)), Some(1));
+        assert_eq!(find_path(&world, HexCoord::new(10, 6), HexCoord::new(10, 14), movement).unwrap().cost, 8);
+        // walking around the end of the river is cheaper than crossing it
+        let path = find_path(&world, HexCoord::new(10, 15), HexCoord::new(11, 16), movement).unwrap();
+        assert_eq!(path.cost, 2);
+        assert_eq!(path.tiles, vec![HexCoord::new(10, 15), HexCoord::new(10, 16), HexCoord::new(11, 16)]);
+    }
+
+    #[test]
+    fn paths_wrap_around_the_date_line() {
+        let world = plains_world(true);
+        let path = find_path(&world, HexCoord::new(1, 10), HexCoord::new(62, 10), MovementType::Land).unwrap();
+        assert_eq!(path.cost, 3);
+        assert!(path.tiles.iter().any(|tile| tile.x == 63));
+        assert_connected(&path, world.bounds());
+    }
+}
@@ -0,0 +1,534 @@
+//! A navigation graph that is kept around between path queries.
+//!
+//! Besides caching the tile-level `MovementGraph`, the map is split into the same 64×64 chunks
+//! as the tilemap. Wherever a unit can step from one chunk into its neighbor, the tiles on both
+//! sides become entrances. A small abstract graph links the entrances of each chunk by the cost
+//! of the cheapest path between them inside the chunk, and links entrances across chunk borders.
+//! Long paths are first found on the abstract graph and then refined chunk by chunk, so a query
+//! only searches the chunks it passes through.
+//!
+//! When tiles change, only the chunks around them are recomputed.
+
+use super::{MIN_STEP_COST, MovementGraph, MovementType, Path};
+use crate::{hex::{HexCoord, HexDirection, MapBounds}, worldgen::{CHUNK_SIZE_HEIGHT, CHUNK_SIZE_WIDTH, WorldData}};
+use petgraph::graphmap::DiGraphMap;
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap, HashSet}};
+
+/// Border runs longer than this get an extra entrance every `MAX_ENTRANCE_SPACING` tiles,
+/// so paths don't have to detour to the middle of a long open border
+const MAX_ENTRANCE_SPACING: usize = 16;
+
+/// Position of a chunk, in chunks
+type ChunkCoord = (i32, i32);
+
+fn chunk_of(coord: HexCoord) -> ChunkCoord {
+    (coord.x / CHUNK_SIZE_WIDTH, coord.y / CHUNK_SIZE_HEIGHT)
+}
+
+/// Key of the border between two chunks, with the lowest chunk first
+fn border_key(a: ChunkCoord, b: ChunkCoord) -> (ChunkCoord, ChunkCoord) {
+    if a < b { (a, b) } else { (b, a) }
+}
+
+/// Cached, chunked movement graph for one kind of unit
+pub struct NavigationGraph {
+    tiles: MovementGraph,
+    bounds: MapBounds,
+    chunks_width: i32,
+    chunks_height: i32,
+    /// Pairs of neighboring tiles a unit can step between, for each border between two chunks.
+    /// Each border is keyed once, by `border_key`, and the first tile of each pair is in the
+    /// first chunk of the key.
+    transitions: HashMap<(ChunkCoord, ChunkCoord), Vec<(HexCoord, HexCoord)>>,
+    /// Entrance tiles, linked by the cost of moving between them
+    entrances: DiGraphMap<HexCoord, u32>,
+}
+
+impl NavigationGraph {
+    pub fn new(world: &WorldData, movement: MovementType) -> Self {
+        let bounds = world.bounds();
+        let mut navigation = NavigationGraph {
+            tiles: MovementGraph::new(world, movement),
+            bounds,
+            chunks_width: (bounds.width + CHUNK_SIZE_WIDTH - 1) / CHUNK_SIZE_WIDTH,
+            chunks_height: (bounds.height + CHUNK_SIZE_HEIGHT - 1) / CHUNK_SIZE_HEIGHT,
+            transitions: HashMap::new(),
+            entrances: DiGraphMap::new(),
+        };
+        let chunks: Vec<ChunkCoord> = navigation.chunks().collect();
+        let borders: HashSet<(ChunkCoord, ChunkCoord)> = chunks
+            .iter()
+            .flat_map(|&chunk| navigation.chunk_borders(chunk))
+            .collect();
+        for border in borders {
+            navigation.find_transitions(border);
+        }
+        for &chunk in chunks.iter() {
+            navigation.link_transitions(chunk);
+        }
+        for &chunk in chunks.iter() {
+            navigation.link_entrances(chunk);
+        }
+        navigation
+    }
+
+    pub fn movement(&self) -> MovementType {
+        self.tiles.movement()
+    }
+
+    /// The cached tile-level graph
+    pub fn tiles(&self) -> &MovementGraph {
+        &self.tiles
+    }
+
+    /// Number of entrance tiles in the abstract graph
+    pub fn entrance_count(&self) -> usize {
+        self.entrances.node_count()
+    }
+
+    fn chunks(&self) -> impl Iterator<Item = ChunkCoord> {
+        let chunks_width = self.chunks_width;
+        (0..self.chunks_height).flat_map(move |cy| (0..chunks_width).map(move |cx| (cx, cy)))
+    }
+
+    /// The chunk and the chunks touching it
+    fn chunk_area(&self, chunk: ChunkCoord) -> Vec<ChunkCoord> {
+        let mut area = Vec::with_capacity(9);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let cy = chunk.1 + dy;
+                let mut cx = chunk.0 + dx;
+                if self.bounds.wrap_horizontal {
+                    cx = cx.rem_euclid(self.chunks_width);
+                }
+                if cx >= 0 && cx < self.chunks_width && cy >= 0 && cy < self.chunks_height && !area.contains(&(cx, cy)) {
+                    area.push((cx, cy));
+                }
+            }
+        }
+        area
+    }
+
+    fn in_chunk(coord: HexCoord, chunk: ChunkCoord) -> bool {
+        chunk_of(coord) == chunk
+    }
+
+    /// Borders between a chunk and the chunks touching it, keyed like `transitions`
+    fn chunk_borders(&self, chunk: ChunkCoord) -> Vec<(ChunkCoord, ChunkCoord)> {
+        self.chunk_area(chunk)
+            .into_iter()
+            .filter(|&other| other != chunk)
+            .map(|other| border_key(chunk, other))
+            .collect()
+    }
+
+    /// Finds the entrances on the border between two chunks, replacing any found before
+    fn find_transitions(&mut self, border: (ChunkCoord, ChunkCoord)) {
+        let (chunk, neighbor_chunk) = border;
+
+        // every step across the border that can also be walked back
+        let mut pairs = Vec::new();
+        let min_x = chunk.0 * CHUNK_SIZE_WIDTH;
+        let min_y = chunk.1 * CHUNK_SIZE_HEIGHT;
+        let max_x = (min_x + CHUNK_SIZE_WIDTH).min(self.bounds.width);
+        let max_y = (min_y + CHUNK_SIZE_HEIGHT).min(self.bounds.height);
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                if x != min_x && x != max_x - 1 && y != min_y && y != max_y - 1 {
+                    continue;
+                }
+                let coord = HexCoord::new(x, y);
+                for &direction in HexDirection::ALL.iter() {
+                    if let Some((nx, ny)) = self.bounds.neighbor(x, y, direction) {
+                        let neighbor = HexCoord::new(nx, ny);
+                        let crossable = self.tiles.step_cost(coord, neighbor).is_some()
+                            && self.tiles.step_cost(neighbor, coord).is_some();
+                        if chunk_of(neighbor) == neighbor_chunk && crossable {
+                            pairs.push((coord, neighbor));
+                        }
+                    }
+                }
+            }
+        }
+
+        // runs of neighboring border tiles are one opening, which needs only a few entrances
+        pairs.sort();
+        pairs.dedup_by_key(|pair| pair.0);
+        let mut entrances = Vec::new();
+        let mut run_start = 0;
+        for i in 1..=pairs.len() {
+            let run_ended = i == pairs.len() || self.bounds.distance(pairs[i - 1].0, pairs[i].0) > 1;
+            if run_ended {
+                let run = &pairs[run_start..i];
+                let count = (run.len() + MAX_ENTRANCE_SPACING - 1) / MAX_ENTRANCE_SPACING;
+                let spacing = run.len() / count;
+                for entrance in 0..count {
+                    entrances.push(run[entrance * spacing + spacing / 2]);
+                }
+                run_start = i;
+            }
+        }
+        if entrances.is_empty() {
+            self.transitions.remove(&border);
+        } else {
+            self.transitions.insert(border, entrances);
+        }
+    }
+
+    /// Adds the entrances of a chunk's borders to the abstract graph, linked across the borders
+    fn link_transitions(&mut self, chunk: ChunkCoord) {
+        let pairs: Vec<(HexCoord, HexCoord)> = self.transitions
+            .iter()
+            .filter(|(&(a, b), _)| a == chunk || b == chunk)
+            .flat_map(|(_, pairs)| pairs.iter().copied())
+            .collect();
+        for (a, b) in pairs {
+            if let Some(cost) = self.tiles.step_cost(a, b) {
+                self.entrances.add_edge(a, b, cost);
+            }
+            if let Some(cost) = self.tiles.step_cost(b, a) {
+                self.entrances.add_edge(b, a, cost);
+            }
+        }
+    }
+
+    /// The entrances of a chunk according to its transitions, which may not be in the graph yet
+    fn transition_entrances(&self, chunk: ChunkCoord) -> HashSet<HexCoord> {
+        self.transitions
+            .iter()
+            .filter(|(&(a, b), _)| a == chunk || b == chunk)
+            .flat_map(|(_, pairs)| pairs.iter().flat_map(|&(a, b)| vec![a, b]))
+            .filter(|&coord| Self::in_chunk(coord, chunk))
+            .collect()
+    }
+
+    fn chunk_entrances(&self, chunk: ChunkCoord) -> Vec<HexCoord> {
+        self.entrances.nodes().filter(|&node| Self::in_chunk(node, chunk)).collect()
+    }
+
+    /// Links every pair of entrances of a chunk that can reach each other without leaving it
+    fn link_entrances(&mut self, chunk: ChunkCoord) {
+        let entrances = self.chunk_entrances(chunk);
+        for &from in entrances.iter() {
+            let costs = self.tiles.costs_within(from, |coord| Self::in_chunk(coord, chunk));
+            for &to in entrances.iter() {
+                if to == from {
+                    continue;
+                }
+                if let Some(&cost) = costs.get(&to) {
+                    self.entrances.add_edge(from, to, cost);
+                }
+            }
+        }
+    }
+
+    /// Updates the graph after a tile changed in the world, such as a road being built or its terrain altered
+    pub fn update_tile(&mut self, world: &WorldData, coord: HexCoord) {
+        self.update_tiles(world, std::iter::once(coord));
+    }
+
+    /// Updates the graph after several tiles changed, recomputing each affected chunk once
+    pub fn update_tiles(&mut self, world: &WorldData, coords: impl IntoIterator<Item = HexCoord>) {
+        let mut changed_chunks = HashSet::new();
+        let mut changed_borders = HashSet::new();
+        for coord in coords {
+            self.tiles.update_tile(world, coord);
+            // steps into the tile from across a border change too
+            let chunk = chunk_of(coord);
+            changed_chunks.insert(chunk);
+            for neighbor in self.bounds.neighbors(coord) {
+                let neighbor_chunk = chunk_of(neighbor);
+                changed_chunks.insert(neighbor_chunk);
+                if neighbor_chunk != chunk {
+                    changed_borders.insert(border_key(chunk, neighbor_chunk));
+                }
+            }
+        }
+        if changed_chunks.is_empty() {
+            return;
+        }
+
+        // changed chunks get new entrances, which can move the entrances of their neighbors too
+        let mut area = HashSet::new();
+        for &chunk in changed_chunks.iter() {
+            area.extend(self.chunk_area(chunk));
+        }
+        let old_entrances: HashMap<ChunkCoord, HashSet<HexCoord>> = area
+            .iter()
+            .map(|&chunk| (chunk, self.chunk_entrances(chunk).into_iter().collect()))
+            .collect();
+        // only borders the changed tiles are on can have gained or lost crossings
+        for border in changed_borders {
+            self.find_transitions(border);
+        }
+        let relink: Vec<ChunkCoord> = area
+            .into_iter()
+            .filter(|chunk| changed_chunks.contains(chunk) || self.transition_entrances(*chunk) != old_entrances[chunk])
+            .collect();
+
+        let stale: Vec<HexCoord> = self.entrances
+            .nodes()
+            .filter(|&node| relink.contains(&chunk_of(node)))
+            .collect();
+        for node in stale {
+            self.entrances.remove_node(node);
+        }
+        for &chunk in relink.iter() {
+            self.link_transitions(chunk);
+        }
+        for &chunk in relink.iter() {
+            self.link_entrances(chunk);
+        }
+    }
+
+    /// The cheapest path between two tiles using the cached tile graph
+    pub fn find_exact_path(&self, start: HexCoord, goal: HexCoord) -> Option<Path> {
+        self.tiles.find_path(start, goal)
+    }
+
+    /// A* over the entrance graph from the start to the goal. The start and goal are linked to
+    /// the entrances of their chunks by extra edges kept outside the graph, so queries share it
+    /// without copying it.
+    fn find_waypoints(
+        &self,
+        start: HexCoord,
+        goal: HexCoord,
+        start_edges: &HashMap<HexCoord, u32>,
+        goal_edges: &HashMap<HexCoord, u32>,
+    ) -> Option<Vec<HexCoord>> {
+        let estimate = |node: HexCoord| self.bounds.distance(node, goal) as u32 * MIN_STEP_COST;
+        let mut costs = HashMap::new();
+        let mut came_from = HashMap::new();
+        let mut open = BinaryHeap::new();
+        costs.insert(start, 0);
+        open.push(Reverse((estimate(start), start)));
+        let mut steps = Vec::new();
+        while let Some(Reverse((score, node))) = open.pop() {
+            let cost = costs[&node];
+            if node == goal {
+                let mut waypoints = vec![goal];
+                while let Some(&previous) = came_from.get(waypoints.last().unwrap()) {
+                    waypoints.push(previous);
+                }
+                waypoints.reverse();
+                return Some(waypoints);
+            }
+            // a cheaper way to this node was found after this entry was queued
+            if score > cost + estimate(node) {
+                continue;
+            }
+
+            steps.clear();
+            if self.entrances.contains_node(node) {
+                steps.extend(self.entrances.edges(node).map(|(_, to, &step_cost)| (to, step_cost)));
+            }
+            if node == start {
+                steps.extend(start_edges.iter().map(|(&to, &step_cost)| (to, step_cost)));
+            }
+            if let Some(&step_cost) = goal_edges.get(&node) {
+                steps.push((goal, step_cost));
+            }
+            for &(next, step_cost) in steps.iter() {
+                let next_cost = cost + step_cost;
+                let cheaper = match costs.get(&next) {
+                    Some(&known) => next_cost < known,
+                    None => true,
+                };
+                if cheaper {
+                    costs.insert(next, next_cost);
+                    came_from.insert(next, node);
+                    open.push(Reverse((next_cost + estimate(next), next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// A path between two tiles found through the chunk entrances. It's close to the cheapest path,
+    /// but may be a little more expensive since it has to pass through entrances.
+    pub fn find_path(&self, start: HexCoord, goal: HexCoord) -> Option<Path> {
+        if !self.bounds.contains(start.x, start.y) || !self.bounds.contains(goal.x, goal.y) {
+            return None;
+        }
+        let (start_chunk, goal_chunk) = (chunk_of(start), chunk_of(goal));
+        if start_chunk == goal_chunk {
+            if let Some(path) = self.tiles.find_path_within(start, goal, |coord| Self::in_chunk(coord, start_chunk)) {
+                return Some(path);
+            }
+        }
+
+        // costs from the start to the entrances of its chunk, and from the goal chunk's entrances to the goal
+        let start_costs = self.tiles.costs_within(start, |coord| Self::in_chunk(coord, start_chunk));
+        let start_edges: HashMap<HexCoord, u32> = self.chunk_entrances(start_chunk)
+            .into_iter()
+            .filter(|&entrance| entrance != start)
+            .filter_map(|entrance| start_costs.get(&entrance).map(|&cost| (entrance, cost)))
+            .collect();
+        let goal_costs = self.tiles.costs_to_within(goal, |coord| Self::in_chunk(coord, goal_chunk));
+        let goal_edges: HashMap<HexCoord, u32> = self.chunk_entrances(goal_chunk)
+            .into_iter()
+            .filter(|&entrance| entrance != goal)
+            .filter_map(|entrance| goal_costs.get(&entrance).map(|&cost| (entrance, cost)))
+            .collect();
+        let waypoints = self.find_waypoints(start, goal, &start_edges, &goal_edges)?;
+
+        // refine each leg between waypoints into tiles
+        let mut path = Path { tiles: vec![start], cost: 0 };
+        for leg in waypoints.windows(2) {
+            let (from, to) = (leg[0], leg[1]);
+            if chunk_of(from) == chunk_of(to) {
+                let chunk = chunk_of(from);
+                let leg_path = self.tiles.find_path_within(from, to, |coord| Self::in_chunk(coord, chunk))?;
+                path.tiles.extend_from_slice(&leg_path.tiles[1..]);
+                path.cost += leg_path.cost;
+            } else {
+                path.tiles.push(to);
+                path.cost += self.tiles.step_cost(from, to)?;
+            }
+        }
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worldgen::{WorldGenSettings, terrain::TerrainType};
+
+    /// A world of plains, two chunks by two
+    fn plains_world(wrap_horizontal: bool) -> WorldData {
+        let mut world = WorldData::new(WorldGenSettings {
+            chunks_width: 2,
+            chunks_height: 2,
+            wrap_horizontal,
+            ..Default::default()
+        });
+        for y in 0..world.height {
+            for x in 0..world.width {
+                world.tile_mut(x, y).terrain_type = TerrainType::Plains;
+            }
+        }
+        world
+    }
+
+    fn assert_valid(path: &Path, navigation: &NavigationGraph, start: HexCoord, goal: HexCoord) {
+        assert_eq!(path.tiles[0], start);
+        assert_eq!(*path.tiles.last().unwrap(), goal);
+        let cost: u32 = path.tiles
+            .windows(2)
+            .map(|pair| navigation.tiles().step_cost(pair[0], pair[1]).expect("path takes an impossible step"))
+            .sum();
+        assert_eq!(cost, path.cost);
+    }
+
+    #[test]
+    fn finds_paths_across_chunks() {
+        let world = plains_world(false);
+        let navigation = NavigationGraph::new(&world, MovementType::Land);
+        let (start, goal) = (HexCoord::new(5, 5), HexCoord::new(120, 110));
+        let path = navigation.find_path(start, goal).unwrap();
+        assert_valid(&path, &navigation, start, goal);
+        let exact = navigation.find_exact_path(start, goal).unwrap();
+        assert!(path.cost >= exact.cost);
+        // going through entrances shouldn't cost much more than the cheapest path
+        assert!(path.cost * 10 <= exact.cost * 11, "{} vs {}", path.cost, exact.cost);
+    }
+
+    #[test]
+    fn paths_within_a_chunk_are_exact() {
+        let world = plains_world(false);
+        let navigation = NavigationGraph::new(&world, MovementType::Land);
+        let (start, goal) = (HexCoord::new(10, 10), HexCoord::new(10, 15));
+        assert_eq!(navigation.find_path(start, goal), navigation.find_exact_path(start, goal));
+    }
+
+    #[test]
+    fn leaves_a_chunk_to_get_around_walls() {
+        let mut world = plains_world(false);
+        // a lake cutting across chunk (0, 0), open only beyond its edge
+        for y in 0..64 {
+            world.tile_mut(32, y).terrain_type = TerrainType::Lake;
+        }
+        let navigation = NavigationGraph::new(&world, MovementType::Land);
+        let (start, goal) = (HexCoord::new(20, 10), HexCoord::new(40, 10));
+        let path = navigation.find_path(start, goal).unwrap();
+        assert_valid(&path, &navigation, start, goal);
+        assert!(path.tiles.iter().any(|tile| tile.y >= 64));
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let mut world = plains_world(false);
+        for y in 0..world.height {
+            world.tile_mut(70, y).terrain_type = TerrainType::DeepOcean;
+        }
+        let navigation = NavigationGraph::new(&world, MovementType::Land);
+        assert_eq!(navigation.find_path(HexCoord::new(10, 10), HexCoord::new(100, 10)), None);
+        assert_eq!(navigation.find_path(HexCoord::new(10, 10), HexCoord::new(70, 10)), None);
+    }
+
+    #[test]
+    fn updates_when_tiles_change() {
+        let mut world = plains_world(false);
+        let mut navigation = NavigationGraph::new(&world, MovementType::Land);
+        let (start, goal) = (HexCoord::new(10, 10), HexCoord::new(100, 10));
+        assert!(navigation.find_path(start, goal).is_some());
+
+        // flooding a column cuts the map in two
+        let column: Vec<HexCoord> = (0..world.height).map(|y| HexCoord::new(70, y)).collect();
+        for coord in column.iter() {
+            world.tile_mut(coord.x, coord.y).terrain_type = TerrainType::Coast;
+        }
+        navigation.update_tiles(&world, column.iter().copied());
+        assert_eq!(navigation.find_path(start, goal), None);
+
+        // and a bridge across it reconnects them
+        let bridge = HexCoord::new(70, 100);
+        world.tile_mut(bridge.x, bridge.y).terrain_type = TerrainType::Plains;
+        navigation.update_tile(&world, bridge);
+        let path = navigation.find_path(start, goal).unwrap();
+        assert_valid(&path, &navigation, start, goal);
+        assert!(path.tiles.contains(&bridge));
+
+        // the result matches a graph built from scratch
+        let rebuilt = NavigationGraph::new(&world, MovementType::Land);
+        let entrances = |navigation: &NavigationGraph| navigation.entrances.nodes().collect::<HashSet<_>>();
+        let edges = |navigation: &NavigationGraph| navigation.entrances
+            .all_edges()
+            .map(|(from, to, &cost)| ((from, to), cost))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(entrances(&navigation), entrances(&rebuilt));
+        assert_eq!(edges(&navigation), edges(&rebuilt));
+        assert_eq!(navigation.find_path(start, goal).map(|path| path.cost), rebuilt.find_path(start, goal).map(|path| path.cost));
+    }
+
+    #[test]
+    fn updates_entrances_on_chunk_borders() {
+        let mut world = plains_world(false);
+        let mut navigation = NavigationGraph::new(&world, MovementType::Land);
+        // most of the last column of the first chunks becomes water, leaving a gap at the top
+        let column: Vec<HexCoord> = (0..100).map(|y| HexCoord::new(CHUNK_SIZE_WIDTH - 1, y)).collect();
+        for coord in column.iter() {
+            world.tile_mut(coord.x, coord.y).terrain_type = TerrainType::Coast;
+        }
+        navigation.update_tiles(&world, column.iter().copied());
+        let rebuilt = NavigationGraph::new(&world, MovementType::Land);
+        assert_eq!(navigation.transitions, rebuilt.transitions);
+        assert_eq!(navigation.entrance_count(), rebuilt.entrance_count());
+        let (start, goal) = (HexCoord::new(10, 10), HexCoord::new(100, 10));
+        let path = navigation.find_path(start, goal).unwrap();
+        assert_valid(&path, &navigation, start, goal);
+        assert!(path.tiles.iter().any(|tile| tile.y >= 100));
+    }
+
+    #[test]
+    fn paths_wrap_around_the_date_line() {
+        let world = plains_world(true);
+        let navigation = NavigationGraph::new(&world, MovementType::Land);
+        let (start, goal) = (HexCoord::new(2, 70), HexCoord::new(125, 70));
+        let path = navigation.find_path(start, goal).unwrap();
+        assert_valid(&path, &navigation, start, goal);
+        assert!(path.tiles.iter().any(|tile| tile.x == 127));
+        assert!(path.cost <= navigation.find_exact_path(start, goal).unwrap().cost + 2);
+    }
+}
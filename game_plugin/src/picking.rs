@@ -0,0 +1,90 @@
+use crate::{GameState, hex::HexCoord, viewport::ViewportCamera, worldgen::WorldData};
+use bevy::{prelude::*, render::camera::Camera};
+
+/// Finds the map tile under the mouse cursor
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<HoveredTile>()
+            .add_event::<TileClicked>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        hovered_tile_update.system()
+                            .label("hovered_tile_update")
+                    )
+                    .with_system(
+                        tile_click.system()
+                            .after("hovered_tile_update")
+                    )
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(clear_hovered_tile.system()));
+    }
+}
+
+/// The map tile under the mouse cursor, if any
+#[derive(Debug, Default)]
+pub struct HoveredTile(pub Option<HexCoord>);
+
+/// Sent when a mouse button is pressed over a map tile
+#[derive(Debug, Copy, Clone)]
+pub struct TileClicked {
+    pub coord: HexCoord,
+    pub button: MouseButton,
+}
+
+/// Converts a cursor position in window coordinates into world space, through the camera's
+/// translation and zoom
+fn cursor_to_world(window: &Window, camera_transform: &Transform, cursor: Vec2) -> Vec2 {
+    let window_size = Vec2::new(window.width(), window.height());
+    // the 2D camera looks at the middle of the window
+    let screen_position = cursor - window_size / 2.0;
+    let world_position = camera_transform.compute_matrix() * screen_position.extend(0.0).extend(1.0);
+    Vec2::new(world_position.x, world_position.y)
+}
+
+fn hovered_tile_update(
+    windows: Res<Windows>,
+    world: Option<Res<WorldData>>,
+    mut hovered_tile: ResMut<HoveredTile>,
+    camera_query: Query<&Transform, (With<Camera>, With<ViewportCamera>)>,
+) {
+    let coord = match (windows.get_primary(), world, camera_query.iter().next()) {
+        (Some(window), Some(world), Some(camera_transform)) => window.cursor_position().and_then(|cursor| {
+            let position = cursor_to_world(window, camera_transform, cursor);
+            // the tilemap sits at the origin, so world space is the same as map space
+            world.bounds().tile_at(position)
+        }),
+        _ => None,
+    };
+    // only write on changes so other systems can rely on change detection
+    if hovered_tile.0 != coord {
+        hovered_tile.0 = coord;
+    }
+}
+
+fn tile_click(
+    mouse_button_input: Res<Input<MouseButton>>,
+    hovered_tile: Res<HoveredTile>,
+    mut events: EventWriter<TileClicked>,
+    interaction_query: Query<&Interaction>,
+) {
+    let coord = match hovered_tile.0 {
+        Some(coord) => coord,
+        None => return,
+    };
+    // clicks on UI buttons shouldn't reach the map underneath
+    if interaction_query.iter().any(|interaction| *interaction != Interaction::None) {
+        return;
+    }
+    for &button in [MouseButton::Left, MouseButton::Right, MouseButton::Middle].iter() {
+        if mouse_button_input.just_pressed(button) {
+            events.send(TileClicked { coord, button });
+        }
+    }
+}
+
+fn clear_hovered_tile(mut hovered_tile: ResMut<HoveredTile>) {
+    hovered_tile.0 = None;
+}
@@ -1,12 +1,19 @@
-use crate::{GameState, loading::{FontAssets, TextureAssets}};
-use bevy::{diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin}, prelude::*};
+use crate::{GameState, calendar::GameDate, loading::{FontAssets, TextureAssets}};
+use bevy::{diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin}, ecs::schedule::ShouldRun, prelude::*};
 
 pub struct PlayStatePlugin;
 
+/// Stage that runs once per simulation tick, any number of times a frame
+pub const SIMULATION_STAGE: &str = "simulation";
+
 impl Plugin for PlayStatePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_plugin(FrameTimeDiagnosticsPlugin::default());
-        app.add_event::<DateEvent>();
+        app.add_event::<NewDay>();
+        app.add_event::<NewMonth>();
+        app.add_event::<NewYear>();
+        app.init_resource::<StartDate>();
+        app.init_resource::<SimulationClock>();
         app.init_resource::<ButtonMaterials>();
         app.add_system_set(
             SystemSet::on_enter(GameState::Playing)
@@ -16,9 +23,15 @@ impl Plugin for PlayStatePlugin {
                 .with_system(setup_date_text.system())
                 .with_system(setup_fps_text.system())
         );
+        app.add_stage_after(
+            CoreStage::Update,
+            SIMULATION_STAGE,
+            SystemStage::parallel()
+                .with_run_criteria(simulation_ticks.system())
+                .with_system(date_tick.system())
+        );
         app.add_system_set(
             SystemSet::on_update(GameState::Playing)
-                .with_system(date_tick.system())
                 .with_system(play_button_update.system())
                 .with_system(date_text_update.system())
                 .with_system(fps_text_update.system())
@@ -48,51 +61,132 @@ enum GameSpeed {
     Fast,
 }
 impl GameSpeed {
-    fn ticks(&self) -> f64 {
+    /// Real time between simulation ticks, in seconds
+    fn tick_length(&self) -> f64 {
         match *self {
-            GameSpeed::Slow => 500.0,
-            GameSpeed::Normal => 200.0,
-            GameSpeed::Fast => 50.0,
+            GameSpeed::Slow => 0.5,
+            GameSpeed::Normal => 0.2,
+            GameSpeed::Fast => 0.05,
         }
     }
 }
 
+/// Most ticks run in one frame. When frames take longer than this many ticks the simulation
+/// slows down rather than stalling the game trying to catch up.
+const MAX_TICKS_PER_FRAME: u32 = 10;
+
+/// Turns frame time into a whole number of fixed length simulation ticks
+#[derive(Debug, Default)]
+pub struct SimulationClock {
+    /// Time that has passed but not been simulated yet, in seconds
+    accumulator: f64,
+    /// Ticks left to run this frame, once counted
+    pending_ticks: Option<u32>,
+}
+
+impl SimulationClock {
+    /// Adds a frame's time and takes the number of ticks that are due
+    fn advance(&mut self, delta_seconds: f64, tick_length: f64) -> u32 {
+        self.accumulator += delta_seconds;
+        let ticks = (self.accumulator / tick_length) as u32;
+        if ticks > MAX_TICKS_PER_FRAME {
+            // drop the backlog so a long frame isn't followed by frames of catching up
+            self.accumulator = 0.0;
+            MAX_TICKS_PER_FRAME
+        } else {
+            self.accumulator -= ticks as f64 * tick_length;
+            ticks
+        }
+    }
+}
+
+/// The date a new game starts on
+pub struct StartDate(pub GameDate);
+
+impl Default for StartDate {
+    fn default() -> Self {
+        StartDate(GameDate::start_of_year(1444))
+    }
+}
+
 pub struct PlayState {
     is_playing: bool,
     game_speed: GameSpeed,
-    date: u32,
+    date: GameDate,
+}
+
+/// Sent every time the date advances
+pub struct NewDay {
+    pub date: GameDate,
+}
+
+/// Sent when the date advances to the first day of a month, after `NewDay`
+pub struct NewMonth {
+    pub date: GameDate,
 }
 
-struct DateEvent {
-    pub date: u32,
+/// Sent when the date advances to 1 January, after `NewMonth`
+pub struct NewYear {
+    pub date: GameDate,
 }
 
 fn setup_date(
     mut commands: Commands,
+    start_date: Res<StartDate>,
 ) {
     commands.spawn().insert(PlayState {
         is_playing: false,
         game_speed: GameSpeed::Fast,
-        date: 0,
+        date: start_date.0,
     });
 }
 
+/// Runs the simulation stage once for every tick due this frame
+fn simulation_ticks(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    mut clock: ResMut<SimulationClock>,
+    query: Query<&PlayState>,
+) -> ShouldRun {
+    let pending_ticks = match clock.pending_ticks {
+        Some(ticks) => ticks,
+        None => {
+            let play_state = match query.single() {
+                Ok(play_state) if play_state.is_playing && *state.current() == GameState::Playing => play_state,
+                _ => {
+                    // don't save up time while paused
+                    clock.accumulator = 0.0;
+                    return ShouldRun::No;
+                }
+            };
+            clock.advance(time.delta_seconds_f64(), play_state.game_speed.tick_length())
+        }
+    };
+    if pending_ticks == 0 {
+        clock.pending_ticks = None;
+        ShouldRun::No
+    } else {
+        clock.pending_ticks = Some(pending_ticks - 1);
+        ShouldRun::YesAndCheckAgain
+    }
+}
+
+/// Advances the date by a day each tick
 fn date_tick(
     mut query: Query<&mut PlayState>,
-    mut last_time: Local<f64>,
-    time: Res<Time>,
-    mut date_event: EventWriter<DateEvent>,
+    mut new_day: EventWriter<NewDay>,
+    mut new_month: EventWriter<NewMonth>,
+    mut new_year: EventWriter<NewYear>,
 ) {
     let mut play_state = query.single_mut().unwrap();
-    if play_state.is_playing {
-        let time_since_update = time.seconds_since_startup() - *last_time;
-        
-        if time_since_update * 1000.0 > play_state.game_speed.ticks() {
-            play_state.date += 1;
-            date_event.send(DateEvent { date: play_state.date });
-            // println!("Date: {}", play_state.date);
-            *last_time = time.seconds_since_startup();
-        }
+    play_state.date = play_state.date.next_day();
+    let date = play_state.date;
+    new_day.send(NewDay { date });
+    if date.is_start_of_month() {
+        new_month.send(NewMonth { date });
+    }
+    if date.is_start_of_year() {
+        new_year.send(NewYear { date });
     }
 }
 
@@ -262,11 +356,44 @@ fn fps_text_update(diagnostics: Res<Diagnostics>, mut query: Query<&mut Text, Wi
 
 fn date_text_update(
     mut query: Query<&mut Text, With<DateText>>,
-    mut events: EventReader<DateEvent>,
+    play_query: Query<&PlayState, Changed<PlayState>>,
 ) {
-    for date_event in events.iter() {
+    // also runs when the play state is first added, to show the start date
+    for play_state in play_query.iter() {
         for mut text in query.iter_mut() {
-            text.sections[1].value = format!("{}", &date_event.date);
+            text.sections[1].value = play_state.date.to_string();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_do_not_depend_on_frame_rate() {
+        // ten seconds at 64 and 4 frames per second, which add up without rounding
+        let tick_length = GameSpeed::Slow.tick_length();
+        let mut fast_frames = SimulationClock::default();
+        let fast_ticks: u32 = (0..640).map(|_| fast_frames.advance(1.0 / 64.0, tick_length)).sum();
+        let mut slow_frames = SimulationClock::default();
+        let slow_ticks: u32 = (0..40).map(|_| slow_frames.advance(1.0 / 4.0, tick_length)).sum();
+        assert_eq!(fast_ticks, 20);
+        assert_eq!(slow_ticks, 20);
+    }
+
+    #[test]
+    fn several_ticks_in_one_frame() {
+        let mut clock = SimulationClock::default();
+        assert_eq!(clock.advance(0.12, GameSpeed::Fast.tick_length()), 2);
+        assert_eq!(clock.advance(0.04, GameSpeed::Fast.tick_length()), 1);
+    }
+
+    #[test]
+    fn ticks_per_frame_are_capped() {
+        let mut clock = SimulationClock::default();
+        assert_eq!(clock.advance(10.0, GameSpeed::Fast.tick_length()), MAX_TICKS_PER_FRAME);
+        // the backlog is dropped rather than caught up on
+        assert_eq!(clock.advance(0.0, GameSpeed::Fast.tick_length()), 0);
+    }
+}
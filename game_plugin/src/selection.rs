@@ -0,0 +1,175 @@
+use crate::{GameState, hex::{HEX_HEIGHT, HEX_WIDTH, HexCoord, MapBounds}, picking::{HoveredTile, TileClicked}, viewport::ViewportCamera, worldgen::WorldData};
+use bevy::{prelude::*, render::texture::{Extent3d, TextureDimension, TextureFormat}};
+
+/// Highlights the tile under the cursor and lets a tile be selected by clicking it
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<SelectedTile>()
+            .init_resource::<OutlineMaterials>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_outlines.system()))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        tile_selection.system()
+                            .label("tile_selection")
+                            .after("hovered_tile_update")
+                    )
+                    .with_system(
+                        outline_update.system()
+                            .after("tile_selection")
+                    )
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(cleanup_outlines.system()));
+    }
+}
+
+/// The tile the player has selected, if any
+#[derive(Debug, Default)]
+pub struct SelectedTile(pub Option<HexCoord>);
+
+/// Outlines are drawn above the tilemap
+const OUTLINE_Z: f32 = 10.0;
+/// Outline thickness in pixels
+const OUTLINE_WIDTH: f32 = 2.0;
+
+struct HoverOutline;
+
+struct SelectionOutline;
+
+struct OutlineMaterials {
+    hover: Handle<ColorMaterial>,
+    selected: Handle<ColorMaterial>,
+}
+
+impl FromWorld for OutlineMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut textures = world.get_resource_mut::<Assets<Texture>>().unwrap();
+        let texture = textures.add(outline_texture());
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        OutlineMaterials {
+            hover: materials.add(ColorMaterial::modulated_texture(texture.clone(), Color::rgba(1.0, 1.0, 1.0, 0.6))),
+            selected: materials.add(ColorMaterial::modulated_texture(texture, Color::rgb(1.0, 0.85, 0.2))),
+        }
+    }
+}
+
+/// Whether a point lies inside a flat-topped hex filling a `HEX_WIDTH` by `HEX_HEIGHT` tile,
+/// shrunk by `inset` pixels. Matches the shape of the hexes in the tileset.
+fn inside_hex(x: f32, y: f32, inset: f32) -> bool {
+    let dx = (x - HEX_WIDTH / 2.0).abs();
+    let dy = (y - HEX_HEIGHT / 2.0).abs();
+    let half_height = HEX_HEIGHT / 2.0 - inset;
+    // the slanted edges go from the middle of the sides to a quarter of the way along the top
+    dy <= half_height && dx <= HEX_WIDTH / 2.0 - inset - dy * (HEX_WIDTH / 4.0) / (HEX_HEIGHT / 2.0)
+}
+
+/// A white hex outline, tinted by the outline materials
+fn outline_texture() -> Texture {
+    let (width, height) = (HEX_WIDTH as u32, HEX_HEIGHT as u32);
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let (center_x, center_y) = (x as f32 + 0.5, y as f32 + 0.5);
+            let on_outline = inside_hex(center_x, center_y, 0.0) && !inside_hex(center_x, center_y, OUTLINE_WIDTH);
+            let alpha = if on_outline { 255 } else { 0 };
+            data.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+    Texture::new(
+        Extent3d::new(width, height, 1),
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn outline_sprite(material: Handle<ColorMaterial>) -> SpriteBundle {
+    SpriteBundle {
+        material,
+        sprite: Sprite::new(Vec2::new(HEX_WIDTH, HEX_HEIGHT)),
+        visible: Visible {
+            is_visible: false,
+            is_transparent: true,
+        },
+        ..Default::default()
+    }
+}
+
+fn setup_outlines(mut commands: Commands, outline_materials: Res<OutlineMaterials>) {
+    commands
+        .spawn_bundle(outline_sprite(outline_materials.hover.clone()))
+        .insert(HoverOutline);
+    commands
+        .spawn_bundle(outline_sprite(outline_materials.selected.clone()))
+        .insert(SelectionOutline);
+}
+
+/// Left click selects a tile, right click or Escape clears the selection
+fn tile_selection(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut clicks: EventReader<TileClicked>,
+    mut selected_tile: ResMut<SelectedTile>,
+) {
+    for click in clicks.iter() {
+        match click.button {
+            MouseButton::Left => selected_tile.0 = Some(click.coord),
+            MouseButton::Right => selected_tile.0 = None,
+            _ => {}
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) && selected_tile.0.is_some() {
+        selected_tile.0 = None;
+    }
+}
+
+/// Places an outline over a tile, on whichever copy of a wrapping map is closest to the camera
+fn place_outline(coord: Option<HexCoord>, bounds: &MapBounds, camera_tile: HexCoord, transform: &mut Transform, visible: &mut Visible) {
+    visible.is_visible = coord.is_some();
+    if let Some(coord) = coord {
+        let copy = bounds.nearest_copy(camera_tile, coord);
+        transform.translation = copy.to_world_position().extend(OUTLINE_Z);
+    }
+}
+
+fn outline_update(
+    hovered_tile: Res<HoveredTile>,
+    selected_tile: Res<SelectedTile>,
+    world: Option<Res<WorldData>>,
+    camera_query: Query<&Transform, With<ViewportCamera>>,
+    camera_moved_query: Query<Entity, (With<ViewportCamera>, Changed<Transform>)>,
+    mut hover_query: Query<(&mut Transform, &mut Visible), (With<HoverOutline>, Without<SelectionOutline>, Without<ViewportCamera>)>,
+    mut selection_query: Query<(&mut Transform, &mut Visible), (With<SelectionOutline>, Without<HoverOutline>, Without<ViewportCamera>)>,
+) {
+    // the nearest copy of a tile changes as the camera pans across the date line
+    let camera_moved = camera_moved_query.iter().next().is_some();
+    if !hovered_tile.is_changed() && !selected_tile.is_changed() && !camera_moved {
+        return;
+    }
+    let (world, camera_transform) = match (world, camera_query.iter().next()) {
+        (Some(world), Some(camera_transform)) => (world, camera_transform),
+        _ => return,
+    };
+    let bounds = world.bounds();
+    let camera_tile = HexCoord::from_world_position(camera_transform.translation.truncate());
+    // the selection outline already marks the selected tile
+    let hovered = hovered_tile.0.filter(|&coord| selected_tile.0 != Some(coord));
+    for (mut transform, mut visible) in hover_query.iter_mut() {
+        place_outline(hovered, &bounds, camera_tile, &mut transform, &mut visible);
+    }
+    for (mut transform, mut visible) in selection_query.iter_mut() {
+        place_outline(selected_tile.0, &bounds, camera_tile, &mut transform, &mut visible);
+    }
+}
+
+fn cleanup_outlines(
+    mut commands: Commands,
+    mut selected_tile: ResMut<SelectedTile>,
+    query: Query<Entity, Or<(With<HoverOutline>, With<SelectionOutline>)>>,
+) {
+    selected_tile.0 = None;
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
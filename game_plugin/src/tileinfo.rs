@@ -0,0 +1,132 @@
+use crate::{GameState, hex::{HexCoord, HexDirection}, loading::FontAssets, selection::SelectedTile, worldgen::WorldData};
+use bevy::prelude::*;
+
+/// Side panel describing the selected tile
+pub struct TileInfoPlugin;
+
+impl Plugin for TileInfoPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_tile_info.system()))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(
+                        tile_info_update.system()
+                            .after("tile_selection")
+                    )
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(cleanup_tile_info.system()));
+    }
+}
+
+const PANEL_WIDTH: f32 = 260.0;
+
+struct TileInfoPanel;
+
+struct TileInfoText;
+
+fn setup_tile_info(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                display: Display::None,
+                size: Size::new(Val::Px(PANEL_WIDTH), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(64.0),
+                    right: Val::Px(0.0),
+                    ..Default::default()
+                },
+                padding: Rect::all(Val::Px(8.0)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.05, 0.05, 0.05, 0.8).into()),
+            ..Default::default()
+        })
+        .insert(TileInfoPanel)
+        // lets the map picking ignore clicks on the panel
+        .insert(Interaction::default())
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: fonts.fira_sans.clone(),
+                            font_size: 18.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(TileInfoText);
+        });
+}
+
+/// Everything known about a tile, one attribute per line
+fn tile_info(world: &WorldData, coord: HexCoord) -> String {
+    let hex_tile = world.tile(coord.x, coord.y);
+    let (x, y) = (coord.x as usize, coord.y as usize);
+    let mut lines = vec![
+        format!("Tile {}, {}", coord.x, coord.y),
+        format!("Terrain: {:?}", hex_tile.terrain_type),
+        format!("Elevation: {:.3} (sea level {:.3})", world.heightmap.get_value(x, y), world.sea_level),
+        format!("Biome: {:?}", hex_tile.biome),
+        format!("Temperature: {:.2}", world.temperature_map.get_value(x, y)),
+        format!("Moisture: {:.2}", world.moisture_map.get_value(x, y)),
+    ];
+    lines.push(match hex_tile.resource {
+        Some(deposit) => format!("Resource: {:?} ({})", deposit.resource, deposit.quantity),
+        None => "Resource: None".to_string(),
+    });
+    let river_flow = HexDirection::ALL.iter().map(|&direction| world.river_flow(coord, direction)).max().unwrap_or(0);
+    if river_flow > 0 {
+        lines.push(format!("River flow: {}", river_flow));
+    }
+    if let Some(id) = hex_tile.landmass {
+        let landmass = world.landmasses.get(id);
+        lines.push(format!("Landmass: {:?} #{} ({} tiles)", landmass.kind, id.0, landmass.area));
+    }
+    if let Some(id) = hex_tile.water_body {
+        let water_body = world.water_bodies.get(id);
+        lines.push(format!("Water body: {:?} #{} ({} tiles)", water_body.kind, id.0, water_body.size));
+    }
+    // there are no nations or units in the game yet, so say so rather than show "None"
+    lines.push("Owner: (not implemented yet)".to_string());
+    lines.push("Units: (not implemented yet)".to_string());
+    lines.join("\n")
+}
+
+fn tile_info_update(
+    selected_tile: Res<SelectedTile>,
+    world: Option<Res<WorldData>>,
+    mut panel_query: Query<&mut Style, With<TileInfoPanel>>,
+    mut text_query: Query<&mut Text, With<TileInfoText>>,
+) {
+    let world = match world {
+        Some(world) => world,
+        None => return,
+    };
+    if !selected_tile.is_changed() && !world.is_changed() {
+        return;
+    }
+    let info = selected_tile.0.map(|coord| tile_info(&world, coord));
+    for mut style in panel_query.iter_mut() {
+        style.display = if info.is_some() { Display::Flex } else { Display::None };
+    }
+    if let Some(info) = info {
+        for mut text in text_query.iter_mut() {
+            text.sections[0].value = info.clone();
+        }
+    }
+}
+
+fn cleanup_tile_info(mut commands: Commands, query: Query<Entity, With<TileInfoPanel>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{GameState, Inspected};
+use crate::{GameState, Inspected, mapview::map_pixel_width, worldgen::WorldData};
 use bevy::{prelude::*, render::camera::{Camera}};
 
 pub struct ViewportPlugin;
@@ -22,6 +22,7 @@ fn viewport_setup(mut commands: Commands,) {
 pub fn viewport_camera(
     time: Res<Time>,
     keyboard_input: Res<Input<KeyCode>>,
+    world: Option<Res<WorldData>>,
     mut query: Query<&mut Transform, (With<Camera>, With<ViewportCamera>)>,
 ) {
     for mut transform in query.iter_mut() {
@@ -54,5 +55,12 @@ pub fn viewport_camera(
         translation_speed *= transform.scale.x;
 
         transform.translation += time.delta_seconds() * direction * translation_speed;
+
+        // scrolling past the date line continues on the other side of the world
+        if let Some(world) = &world {
+            if world.settings.wrap_horizontal {
+                transform.translation.x = transform.translation.x.rem_euclid(map_pixel_width(world));
+            }
+        }
     }
 }
@@ -0,0 +1,286 @@
+pub mod climate;
+pub mod erosion;
+pub mod landmass;
+pub mod pipeline;
+pub mod presets;
+pub mod resources;
+pub mod rivers;
+pub mod tectonics;
+pub mod terrain;
+pub mod water;
+
+use crate::{hex::{HexCoord, HexDirection, MapBounds}, worldgen::{climate::Biome, landmass::{LandmassId, Landmasses}, pipeline::WorldGenPipeline, resources::ResourceDeposit, rivers::{OWNED_EDGES, edge_owner}, tectonics::PlateMap, terrain::TerrainType, water::{WaterBodies, WaterBodyId}}};
+use bevy::prelude::*;
+use chickenwire::{coordinate::CoordSys, hexgrid::{Parity, Tilt}, prelude::HexGrid};
+use noise::{*, utils::{*}};
+
+pub const CHUNK_SIZE_WIDTH: i32 = 64;
+pub const CHUNK_SIZE_HEIGHT: i32 = 64;
+
+/// Latitude bounds the noise maps sample the sphere with
+pub const LATITUDE_BOUNDS: (f64, f64) = (-90., 90.);
+/// Longitude bounds the noise maps sample the sphere with
+pub const LONGITUDE_BOUNDS: (f64, f64) = (-180., 180.);
+
+/// Generates worlds without any rendering, so it also works in headless apps using `MinimalPlugins`.
+///
+/// Send a `GenerateWorldEvent` to generate a world from the current `WorldGenSettings`
+/// using the `WorldGenPipeline` resource. The result is inserted as the `WorldData` resource.
+pub struct WorldGenPlugin;
+
+impl Plugin for WorldGenPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<WorldGenSettings>()
+            .init_resource::<WorldGenPipeline>()
+            .add_event::<GenerateWorldEvent>()
+            .add_system(generate_world_system.system());
+    }
+}
+
+pub struct GenerateWorldEvent;
+
+fn generate_world_system(
+    mut commands: Commands,
+    mut events: EventReader<GenerateWorldEvent>,
+    settings: Res<WorldGenSettings>,
+    pipeline: Res<WorldGenPipeline>,
+) {
+    // several requests in the same frame only need one world
+    if events.iter().count() == 0 {
+        return;
+    }
+    let world = pipeline.run(settings.clone());
+    // also kept as resources of their own for systems that only need them
+    commands.insert_resource(world.water_bodies.clone());
+    commands.insert_resource(world.landmasses.clone());
+    commands.insert_resource(world);
+}
+
+/// Generates a world with the standard pipeline, without needing a Bevy app
+pub fn generate_world(settings: WorldGenSettings) -> WorldData {
+    WorldGenPipeline::default().run(settings)
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct HexTile {
+    pub terrain_type: TerrainType,
+    pub biome: Biome,
+    /// River flow along the north, north-east and south-east edges, indexed by `HexDirection`.
+    /// Zero means no river. The other edges belong to the neighbors on those sides, so look
+    /// rivers up with `WorldData::river_flow`.
+    pub rivers: [u32; OWNED_EDGES],
+    /// The ocean, sea or lake this tile is part of
+    pub water_body: Option<WaterBodyId>,
+    /// The continent, island or islet this tile is part of
+    pub landmass: Option<LandmassId>,
+    /// Natural resource that can be extracted from this tile
+    pub resource: Option<ResourceDeposit>,
+}
+
+impl Default for HexTile {
+    fn default() -> Self {
+        HexTile {
+            terrain_type: TerrainType::DeepOcean,
+            biome: Biome::Water,
+            rivers: [0; OWNED_EDGES],
+            water_body: None,
+            landmass: None,
+            resource: None,
+        }
+    }
+}
+
+/// Everything world generation produces, shared between the `WorldGenStep`s of a `WorldGenPipeline`
+pub struct WorldData {
+    pub settings: WorldGenSettings,
+    pub width: i32,
+    pub height: i32,
+    pub heightmap: NoiseMap,
+    pub sea_level: f64,
+    /// Temperature from `0.0` to `1.0`
+    pub temperature_map: NoiseMap,
+    /// Moisture from `0.0` to `1.0`
+    pub moisture_map: NoiseMap,
+    pub plates: Option<PlateMap>,
+    pub hex_grid: HexGrid<HexTile>,
+    pub water_bodies: WaterBodies,
+    pub landmasses: Landmasses,
+}
+
+impl WorldData {
+    /// An empty world of the size given by the settings, with every tile deep ocean
+    pub fn new(settings: WorldGenSettings) -> Self {
+        let width = settings.map_width();
+        let height = settings.map_height();
+        let mut hex_grid = HexGrid::<HexTile>::new(Tilt::Flat, Parity::Even, CoordSys::Offset);
+        for x in 0..width {
+            for y in 0..height {
+                hex_grid.add(HexCoord::new(x, y).into(), HexTile::default()).unwrap();
+            }
+        }
+        WorldData {
+            sea_level: settings.sea_level,
+            settings,
+            width,
+            height,
+            heightmap: NoiseMap::new(width as usize, height as usize),
+            temperature_map: NoiseMap::new(width as usize, height as usize),
+            moisture_map: NoiseMap::new(width as usize, height as usize),
+            plates: None,
+            hex_grid,
+            water_bodies: WaterBodies::default(),
+            landmasses: Landmasses::default(),
+        }
+    }
+
+    pub fn bounds(&self) -> MapBounds {
+        self.settings.map_bounds()
+    }
+
+    pub fn tile(&self, x: i32, y: i32) -> &HexTile {
+        self.hex_grid.get(HexCoord::new(x, y).into()).unwrap()
+    }
+
+    pub fn tile_mut(&mut self, x: i32, y: i32) -> &mut HexTile {
+        self.hex_grid.get_mut(HexCoord::new(x, y).into()).unwrap()
+    }
+
+    /// River flow along an edge of a tile. Zero means no river.
+    pub fn river_flow(&self, coord: HexCoord, direction: HexDirection) -> u32 {
+        match edge_owner(self.bounds(), coord.x, coord.y, direction) {
+            Some(((x, y), edge)) => self.tile(x, y).rivers[edge],
+            None => 0,
+        }
+    }
+
+    /// Whether a river runs along any edge of a tile
+    pub fn has_river(&self, coord: HexCoord) -> bool {
+        HexDirection::ALL.iter().any(|&direction| self.river_flow(coord, direction) > 0)
+    }
+}
+
+/// Parameters controlling world generation.
+///
+/// The defaults reproduce the original hardcoded map.
+#[derive(Debug, Clone)]
+pub struct WorldGenSettings {
+    pub seed: u32,
+    /// Map width in chunks of `CHUNK_SIZE_WIDTH` tiles
+    pub chunks_width: i32,
+    /// Map height in chunks of `CHUNK_SIZE_HEIGHT` tiles
+    pub chunks_height: i32,
+    /// Heightmap values below this are water
+    pub sea_level: f64,
+    /// Fraction of the map that should be land (0.0 - 1.0).
+    /// When set, the sea level is derived from the heightmap instead of `sea_level`.
+    pub land_percentage: Option<f64>,
+    pub noise_octaves: usize,
+    pub noise_frequency: f64,
+    pub noise_persistence: f64,
+    pub noise_lacunarity: f64,
+    /// Number of tectonic plates to simulate. Zero disables plate tectonics.
+    pub plate_count: u32,
+    /// How strongly plates and their boundaries affect the heightmap
+    pub plate_uplift: f64,
+    /// Number of rain droplets simulated for hydraulic erosion. Zero disables it.
+    pub hydraulic_erosion_iterations: u32,
+    /// Number of thermal erosion passes. Zero disables it.
+    pub thermal_erosion_iterations: u32,
+    /// Connect the east and west edges of the map, matching the heightmap noise which
+    /// already wraps around the sphere
+    pub wrap_horizontal: bool,
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> Self {
+        WorldGenSettings {
+            seed: 1234,
+            chunks_width: 6,
+            chunks_height: 3,
+            sea_level: 0.05,
+            land_percentage: None,
+            noise_octaves: Fbm::DEFAULT_OCTAVE_COUNT,
+            noise_frequency: 1.0,
+            noise_persistence: 0.5,
+            noise_lacunarity: 2.0,
+            plate_count: 0,
+            plate_uplift: 0.3,
+            hydraulic_erosion_iterations: 0,
+            thermal_erosion_iterations: 0,
+            wrap_horizontal: false,
+        }
+    }
+}
+
+impl WorldGenSettings {
+    pub fn map_width(&self) -> i32 {
+        self.chunks_width * CHUNK_SIZE_WIDTH
+    }
+
+    pub fn map_height(&self) -> i32 {
+        self.chunks_height * CHUNK_SIZE_HEIGHT
+    }
+
+    pub fn map_bounds(&self) -> MapBounds {
+        MapBounds {
+            width: self.map_width(),
+            height: self.map_height(),
+            wrap_horizontal: self.wrap_horizontal,
+        }
+    }
+
+    /// The sea level to use for this heightmap, taking `land_percentage` into account
+    pub fn effective_sea_level(&self, heightmap: &NoiseMap) -> f64 {
+        match self.land_percentage {
+            Some(land_percentage) => sea_level_for_land_percentage(heightmap, land_percentage),
+            None => self.sea_level,
+        }
+    }
+}
+
+/// Finds the height at which the given fraction of the heightmap is land
+pub fn sea_level_for_land_percentage(heightmap: &NoiseMap, land_percentage: f64) -> f64 {
+    let (width, height) = heightmap.size();
+    let mut values: Vec<f64> = Vec::with_capacity(width * height);
+    for x in 0..width {
+        for y in 0..height {
+            values.push(heightmap.get_value(x, y));
+        }
+    }
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let water_fraction = 1.0 - land_percentage.clamp(0.0, 1.0);
+    let index = ((values.len() as f64 * water_fraction) as usize).min(values.len() - 1);
+    values[index]
+}
+
+/// Builds the Fbm noise function used for the world's noise layers.
+/// `seed_offset` lets each layer get its own noise from the same world seed.
+pub fn world_noise(settings: &WorldGenSettings, seed_offset: u32) -> Fbm {
+    Fbm::new()
+        .set_seed(settings.seed.wrapping_add(seed_offset))
+        .set_octaves(settings.noise_octaves)
+        .set_persistence(settings.noise_persistence)
+        .set_frequency(settings.noise_frequency)
+        .set_lacunarity(settings.noise_lacunarity)
+}
+
+/// Samples a noise function over the whole sphere at the map's resolution
+pub fn build_sphere_map(settings: &WorldGenSettings, noise: &dyn NoiseFn<[f64; 3]>) -> NoiseMap {
+    SphereMapBuilder::new(noise)
+        .set_bounds(LATITUDE_BOUNDS.0, LATITUDE_BOUNDS.1, LONGITUDE_BOUNDS.0, LONGITUDE_BOUNDS.1)
+        .set_size(settings.map_width() as usize, settings.map_height() as usize)
+        .build()
+}
+
+/// Latitude in degrees of a map row, matching how `SphereMapBuilder` samples rows
+pub fn latitude(y: usize, map_height: usize) -> f64 {
+    let step = (LATITUDE_BOUNDS.1 - LATITUDE_BOUNDS.0) / map_height as f64;
+    LATITUDE_BOUNDS.0 + step * y as f64
+}
+
+pub fn generate_heightmap(settings: &WorldGenSettings) -> NoiseMap {
+    build_sphere_map(settings, &world_noise(settings, 0))
+}
@@ -0,0 +1,101 @@
+use crate::worldgen::{WorldGenSettings, build_sphere_map, latitude, world_noise};
+use noise::utils::NoiseMap;
+
+/// Seed offsets so each climate layer gets noise independent of the heightmap
+const TEMPERATURE_SEED_OFFSET: u32 = 1;
+const MOISTURE_SEED_OFFSET: u32 = 2;
+
+/// How much colder it gets per unit of height above sea level
+const ELEVATION_COOLING: f64 = 0.8;
+/// How much the temperature noise can shift the latitude-based temperature
+const TEMPERATURE_VARIATION: f64 = 0.1;
+/// Nine in ten moisture noise values fall within `-0.33..0.33`, so they are stretched to cover
+/// the whole moisture range and reach the dry and wet ends of the biome table
+const MOISTURE_CONTRAST: f64 = 1.5;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Water,
+    Ice,
+    Tundra,
+    Taiga,
+    Grassland,
+    TemperateForest,
+    TemperateRainforest,
+    Desert,
+    Savanna,
+    TropicalRainforest,
+}
+
+impl Biome {
+    /// Whittaker-style lookup from temperature and moisture, both in `0.0..=1.0`
+    pub fn from_climate(temperature: f64, moisture: f64) -> Biome {
+        if temperature < 0.1 {
+            Biome::Ice
+        } else if temperature < 0.25 {
+            Biome::Tundra
+        } else if temperature < 0.4 {
+            if moisture < 0.25 {
+                Biome::Tundra
+            } else {
+                Biome::Taiga
+            }
+        } else if temperature < 0.7 {
+            if moisture < 0.2 {
+                Biome::Desert
+            } else if moisture < 0.45 {
+                Biome::Grassland
+            } else if moisture < 0.75 {
+                Biome::TemperateForest
+            } else {
+                Biome::TemperateRainforest
+            }
+        } else if moisture < 0.25 {
+            Biome::Desert
+        } else if moisture < 0.6 {
+            Biome::Savanna
+        } else {
+            Biome::TropicalRainforest
+        }
+    }
+
+    pub fn is_forest(&self) -> bool {
+        matches!(
+            self,
+            Biome::Taiga | Biome::TemperateForest | Biome::TemperateRainforest | Biome::TropicalRainforest
+        )
+    }
+}
+
+/// Temperature from `0.0` (coldest) to `1.0` (hottest).
+/// Falls off towards the poles and with elevation above sea level, with some noise for variety.
+pub fn generate_temperature_map(settings: &WorldGenSettings, heightmap: &NoiseMap, sea_level: f64) -> NoiseMap {
+    let (width, height) = heightmap.size();
+    let variation = build_sphere_map(settings, &world_noise(settings, TEMPERATURE_SEED_OFFSET));
+    let mut temperature_map = NoiseMap::new(width, height);
+    for y in 0..height {
+        let latitude_temperature = latitude(y, height).to_radians().cos();
+        for x in 0..width {
+            let elevation = (heightmap.get_value(x, y) - sea_level).max(0.0);
+            let temperature = latitude_temperature
+                - elevation * ELEVATION_COOLING
+                + variation.get_value(x, y) * TEMPERATURE_VARIATION;
+            temperature_map.set_value(x, y, temperature.clamp(0.0, 1.0));
+        }
+    }
+    temperature_map
+}
+
+/// Moisture from `0.0` (driest) to `1.0` (wettest)
+pub fn generate_moisture_map(settings: &WorldGenSettings) -> NoiseMap {
+    let noise_map = build_sphere_map(settings, &world_noise(settings, MOISTURE_SEED_OFFSET));
+    let (width, height) = noise_map.size();
+    let mut moisture_map = NoiseMap::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let moisture = noise_map.get_value(x, y) * MOISTURE_CONTRAST + 0.5;
+            moisture_map.set_value(x, y, moisture.clamp(0.0, 1.0));
+        }
+    }
+    moisture_map
+}
@@ -0,0 +1,192 @@
+use crate::worldgen::WorldGenSettings;
+use noise::utils::NoiseMap;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const EROSION_SEED_OFFSET: u64 = 4;
+
+// Hydraulic erosion
+/// Steps a droplet lives for before evaporating completely
+const DROPLET_LIFETIME: usize = 30;
+/// How much a droplet keeps its direction instead of following the slope
+const INERTIA: f64 = 0.05;
+/// Sediment a droplet can carry per unit of slope, speed and water
+const SEDIMENT_CAPACITY: f64 = 4.0;
+const MIN_SEDIMENT_CAPACITY: f64 = 0.0001;
+/// Fraction of free capacity eroded per step
+const ERODE_SPEED: f64 = 0.3;
+/// Fraction of excess sediment deposited per step
+const DEPOSIT_SPEED: f64 = 0.3;
+const EVAPORATE_SPEED: f64 = 0.02;
+const GRAVITY: f64 = 4.0;
+
+// Thermal erosion
+/// Height difference between neighboring cells above which material slumps downhill
+const TALUS: f64 = 0.02;
+/// Fraction of the excess height moved per pass
+const THERMAL_RATE: f64 = 0.25;
+
+/// Heightmap values copied out of a `NoiseMap` for fast access
+struct Heights {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+}
+
+impl Heights {
+    fn from_noise_map(noise_map: &NoiseMap) -> Self {
+        let (width, height) = noise_map.size();
+        let mut values = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                values.push(noise_map.get_value(x, y));
+            }
+        }
+        Heights { width, height, values }
+    }
+
+    fn write_to(&self, noise_map: &mut NoiseMap) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                noise_map.set_value(x, y, self.values[x + y * self.width]);
+            }
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> f64 {
+        self.values[x + y * self.width]
+    }
+
+    /// Bilinearly interpolated height and gradient at a position within the map
+    fn height_and_gradient(&self, x: f64, y: f64) -> (f64, f64, f64) {
+        let (cell_x, cell_y) = (x as usize, y as usize);
+        let (u, v) = (x - cell_x as f64, y - cell_y as f64);
+        let nw = self.get(cell_x, cell_y);
+        let ne = self.get(cell_x + 1, cell_y);
+        let sw = self.get(cell_x, cell_y + 1);
+        let se = self.get(cell_x + 1, cell_y + 1);
+        let gradient_x = (ne - nw) * (1.0 - v) + (se - sw) * v;
+        let gradient_y = (sw - nw) * (1.0 - u) + (se - ne) * u;
+        let height = nw * (1.0 - u) * (1.0 - v) + ne * u * (1.0 - v) + sw * (1.0 - u) * v + se * u * v;
+        (height, gradient_x, gradient_y)
+    }
+
+    /// Spreads a height change over the four cells around a position
+    fn add_bilinear(&mut self, x: f64, y: f64, amount: f64) {
+        let (cell_x, cell_y) = (x as usize, y as usize);
+        let (u, v) = (x - cell_x as f64, y - cell_y as f64);
+        let width = self.width;
+        self.values[cell_x + cell_y * width] += amount * (1.0 - u) * (1.0 - v);
+        self.values[cell_x + 1 + cell_y * width] += amount * u * (1.0 - v);
+        self.values[cell_x + (cell_y + 1) * width] += amount * (1.0 - u) * v;
+        self.values[cell_x + 1 + (cell_y + 1) * width] += amount * u * v;
+    }
+
+    fn in_bounds(&self, x: f64, y: f64) -> bool {
+        x >= 0.0 && y >= 0.0 && x < (self.width - 1) as f64 && y < (self.height - 1) as f64
+    }
+}
+
+/// Simulates a single rain droplet running downhill, carving and depositing sediment
+fn simulate_droplet(heights: &mut Heights, mut x: f64, mut y: f64) {
+    let (mut direction_x, mut direction_y) = (0.0, 0.0);
+    let mut speed = 1.0;
+    let mut water = 1.0;
+    let mut sediment = 0.0;
+
+    for _ in 0..DROPLET_LIFETIME {
+        let (old_height, gradient_x, gradient_y) = heights.height_and_gradient(x, y);
+        direction_x = direction_x * INERTIA - gradient_x * (1.0 - INERTIA);
+        direction_y = direction_y * INERTIA - gradient_y * (1.0 - INERTIA);
+        let length = (direction_x * direction_x + direction_y * direction_y).sqrt();
+        if length == 0.0 {
+            break;
+        }
+        direction_x /= length;
+        direction_y /= length;
+
+        let (old_x, old_y) = (x, y);
+        x += direction_x;
+        y += direction_y;
+        if !heights.in_bounds(x, y) {
+            break;
+        }
+
+        let (new_height, _, _) = heights.height_and_gradient(x, y);
+        let delta_height = new_height - old_height;
+        let capacity = (-delta_height * speed * water * SEDIMENT_CAPACITY).max(MIN_SEDIMENT_CAPACITY);
+
+        if sediment > capacity || delta_height > 0.0 {
+            // moving uphill fills the pit behind the droplet, otherwise drop what can't be carried
+            let deposit = if delta_height > 0.0 {
+                delta_height.min(sediment)
+            } else {
+                (sediment - capacity) * DEPOSIT_SPEED
+            };
+            sediment -= deposit;
+            heights.add_bilinear(old_x, old_y, deposit);
+        } else {
+            let erode = ((capacity - sediment) * ERODE_SPEED).min(-delta_height);
+            sediment += erode;
+            heights.add_bilinear(old_x, old_y, -erode);
+        }
+
+        speed = (speed * speed + delta_height * GRAVITY).max(0.0).sqrt();
+        water *= 1.0 - EVAPORATE_SPEED;
+    }
+}
+
+/// Moves material from cells to lower neighbors wherever the slope is steeper than `TALUS`
+fn thermal_pass(heights: &mut Heights) {
+    let (width, height) = (heights.width, heights.height);
+    let mut deltas = vec![0.0; heights.values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let current = heights.get(x, y);
+            let neighbors = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for &(nx, ny) in neighbors.iter() {
+                if nx >= width || ny >= height {
+                    continue;
+                }
+                let difference = current - heights.get(nx, ny);
+                if difference > TALUS {
+                    let amount = (difference - TALUS) * THERMAL_RATE / 4.0;
+                    deltas[x + y * width] -= amount;
+                    deltas[nx + ny * width] += amount;
+                }
+            }
+        }
+    }
+    for (value, delta) in heights.values.iter_mut().zip(deltas.iter()) {
+        *value += delta;
+    }
+}
+
+/// Runs the hydraulic and thermal erosion passes configured in the settings.
+/// Droplet positions come from the world seed, so the result is deterministic.
+pub fn erode_heightmap(settings: &WorldGenSettings, heightmap: &mut NoiseMap) {
+    if settings.hydraulic_erosion_iterations == 0 && settings.thermal_erosion_iterations == 0 {
+        return;
+    }
+    let mut heights = Heights::from_noise_map(heightmap);
+    if heights.width < 2 || heights.height < 2 {
+        return;
+    }
+
+    let mut rng = StdRng::seed_from_u64(settings.seed as u64 + EROSION_SEED_OFFSET);
+    for _ in 0..settings.hydraulic_erosion_iterations {
+        let x = rng.gen_range(0.0..(heights.width - 1) as f64);
+        let y = rng.gen_range(0.0..(heights.height - 1) as f64);
+        simulate_droplet(&mut heights, x, y);
+    }
+
+    for _ in 0..settings.thermal_erosion_iterations {
+        thermal_pass(&mut heights);
+    }
+
+    heights.write_to(heightmap);
+}
@@ -0,0 +1,106 @@
+use crate::{hex::{HexCoord, HexDirection, MapBounds}, worldgen::{HexTile, terrain::TerrainType}};
+use chickenwire::prelude::HexGrid;
+use std::collections::VecDeque;
+
+/// Landmasses at least this many tiles are continents
+const CONTINENT_MIN_AREA: usize = 1000;
+/// Landmasses at least this many tiles are islands, smaller ones are islets
+const ISLAND_MIN_AREA: usize = 20;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct LandmassId(pub u32);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LandmassKind {
+    Continent,
+    Island,
+    Islet,
+}
+
+#[derive(Debug, Clone)]
+pub struct Landmass {
+    pub id: LandmassId,
+    pub tiles: Vec<(i32, i32)>,
+    pub area: usize,
+    /// Number of tile edges bordering the ocean or a sea
+    pub coastline_length: usize,
+    pub kind: LandmassKind,
+}
+
+/// All landmasses on the map, indexed by `LandmassId`
+#[derive(Debug, Clone, Default)]
+pub struct Landmasses {
+    pub landmasses: Vec<Landmass>,
+}
+
+impl Landmasses {
+    pub fn get(&self, id: LandmassId) -> &Landmass {
+        &self.landmasses[id.0 as usize]
+    }
+}
+
+fn terrain_at(hex_grid: &HexGrid<HexTile>, x: i32, y: i32) -> TerrainType {
+    hex_grid.get(HexCoord::new(x, y).into()).unwrap().terrain_type
+}
+
+/// Labels every land tile with the connected landmass it belongs to
+pub fn detect_landmasses(hex_grid: &mut HexGrid<HexTile>, bounds: MapBounds) -> Landmasses {
+    let (width, height) = (bounds.width, bounds.height);
+    let mut visited = vec![false; (width * height) as usize];
+    let mut landmasses = Landmasses::default();
+
+    for y in 0..height {
+        for x in 0..width {
+            if visited[(x + y * width) as usize] || terrain_at(hex_grid, x, y).is_water() {
+                continue;
+            }
+
+            let mut tiles = Vec::new();
+            let mut coastline_length = 0;
+            let mut queue = VecDeque::new();
+            visited[(x + y * width) as usize] = true;
+            queue.push_back((x, y));
+            while let Some((tx, ty)) = queue.pop_front() {
+                tiles.push((tx, ty));
+                for &direction in HexDirection::ALL.iter() {
+                    if let Some((nx, ny)) = bounds.neighbor(tx, ty, direction) {
+                        let terrain_type = terrain_at(hex_grid, nx, ny);
+                        if terrain_type.is_water() {
+                            if terrain_type != TerrainType::Lake {
+                                coastline_length += 1;
+                            }
+                            continue;
+                        }
+                        let index = (nx + ny * width) as usize;
+                        if !visited[index] {
+                            visited[index] = true;
+                            queue.push_back((nx, ny));
+                        }
+                    }
+                }
+            }
+
+            let area = tiles.len();
+            let kind = if area >= CONTINENT_MIN_AREA {
+                LandmassKind::Continent
+            } else if area >= ISLAND_MIN_AREA {
+                LandmassKind::Island
+            } else {
+                LandmassKind::Islet
+            };
+            let id = LandmassId(landmasses.landmasses.len() as u32);
+            for &(tx, ty) in tiles.iter() {
+                hex_grid.get_mut(HexCoord::new(tx, ty).into()).unwrap().landmass = Some(id);
+            }
+            landmasses.landmasses.push(Landmass {
+                id,
+                tiles,
+                area,
+                coastline_length,
+                kind,
+            });
+        }
+    }
+
+    landmasses
+}
@@ -0,0 +1,384 @@
+use crate::worldgen::{
+    WorldData, WorldGenSettings, generate_heightmap,
+    climate::{Biome, generate_moisture_map, generate_temperature_map},
+    erosion::erode_heightmap,
+    landmass::detect_landmasses,
+    resources::place_resources,
+    rivers::generate_rivers,
+    tectonics::apply_plate_tectonics,
+    terrain::TerrainType,
+    water::detect_water_bodies,
+};
+
+/// A single stage of world generation, reading and writing the shared `WorldData`.
+///
+/// Steps run in pipeline order and can rely on the output of the steps before them.
+pub trait WorldGenStep: Send + Sync {
+    /// Unique name used to find the step in a `WorldGenPipeline`
+    fn name(&self) -> &'static str;
+
+    fn run(&self, world: &mut WorldData);
+}
+
+struct PipelineEntry {
+    step: Box<dyn WorldGenStep>,
+    enabled: bool,
+}
+
+/// An ordered list of world generation steps
+pub struct WorldGenPipeline {
+    steps: Vec<PipelineEntry>,
+}
+
+impl Default for WorldGenPipeline {
+    /// The standard pipeline used for new games
+    fn default() -> Self {
+        let mut pipeline = WorldGenPipeline::empty();
+        pipeline
+            .add_step(HeightmapStep)
+            .add_step(TectonicsStep)
+            .add_step(ErosionStep)
+            .add_step(TerrainStep)
+            .add_step(ClimateStep)
+            .add_step(BiomeStep)
+            .add_step(RiverStep)
+            .add_step(WaterBodyStep)
+            .add_step(LandmassStep)
+            .add_step(ResourceStep);
+        pipeline
+    }
+}
+
+impl WorldGenPipeline {
+    pub fn empty() -> Self {
+        WorldGenPipeline { steps: Vec::new() }
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.steps.iter().position(|entry| entry.step.name() == name)
+    }
+
+    /// Adds a step to the end of the pipeline
+    pub fn add_step<S: WorldGenStep + 'static>(&mut self, step: S) -> &mut Self {
+        self.steps.push(PipelineEntry {
+            step: Box::new(step),
+            enabled: true,
+        });
+        self
+    }
+
+    /// Inserts a step before the named step, or at the end if there is no such step
+    pub fn insert_step_before<S: WorldGenStep + 'static>(&mut self, before: &str, step: S) -> &mut Self {
+        let index = self.position(before).unwrap_or(self.steps.len());
+        self.steps.insert(index, PipelineEntry {
+            step: Box::new(step),
+            enabled: true,
+        });
+        self
+    }
+
+    /// Moves the named step so it runs just before another step
+    pub fn move_step_before(&mut self, name: &str, before: &str) -> &mut Self {
+        if let Some(index) = self.position(name) {
+            let entry = self.steps.remove(index);
+            let index = self.position(before).unwrap_or(self.steps.len());
+            self.steps.insert(index, entry);
+        }
+        self
+    }
+
+    pub fn remove_step(&mut self, name: &str) -> &mut Self {
+        self.steps.retain(|entry| entry.step.name() != name);
+        self
+    }
+
+    /// Disabled steps stay in place but are skipped when the pipeline runs
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> &mut Self {
+        if let Some(index) = self.position(name) {
+            self.steps[index].enabled = enabled;
+        }
+        self
+    }
+
+    /// Names of the steps in the order they run
+    pub fn step_names(&self) -> Vec<&'static str> {
+        self.steps.iter().map(|entry| entry.step.name()).collect()
+    }
+
+    /// Generates a new world from the settings
+    pub fn run(&self, settings: WorldGenSettings) -> WorldData {
+        let mut world = WorldData::new(settings);
+        self.run_on(&mut world);
+        world
+    }
+
+    /// Runs the enabled steps over existing world data
+    pub fn run_on(&self, world: &mut WorldData) {
+        for entry in self.steps.iter().filter(|entry| entry.enabled) {
+            entry.step.run(world);
+        }
+    }
+}
+
+/// Samples the base heightmap from noise
+pub struct HeightmapStep;
+
+impl WorldGenStep for HeightmapStep {
+    fn name(&self) -> &'static str {
+        "heightmap"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        world.heightmap = generate_heightmap(&world.settings);
+    }
+}
+
+/// Shapes continents and mountain ranges with plate tectonics
+pub struct TectonicsStep;
+
+impl WorldGenStep for TectonicsStep {
+    fn name(&self) -> &'static str {
+        "tectonics"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        world.plates = apply_plate_tectonics(&world.settings, &mut world.heightmap);
+    }
+}
+
+pub struct ErosionStep;
+
+impl WorldGenStep for ErosionStep {
+    fn name(&self) -> &'static str {
+        "erosion"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        erode_heightmap(&world.settings, &mut world.heightmap);
+    }
+}
+
+/// Decides the sea level and classifies each tile's terrain from the heightmap
+pub struct TerrainStep;
+
+impl WorldGenStep for TerrainStep {
+    fn name(&self) -> &'static str {
+        "terrain"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        world.sea_level = world.settings.effective_sea_level(&world.heightmap);
+        for x in 0..world.width {
+            for y in 0..world.height {
+                let height = world.heightmap.get_value(x as usize, y as usize);
+                let sea_level = world.sea_level;
+                world.tile_mut(x, y).terrain_type = TerrainType::from_elevation(height, sea_level);
+            }
+        }
+    }
+}
+
+/// Generates the temperature and moisture maps
+pub struct ClimateStep;
+
+impl WorldGenStep for ClimateStep {
+    fn name(&self) -> &'static str {
+        "climate"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        world.temperature_map = generate_temperature_map(&world.settings, &world.heightmap, world.sea_level);
+        world.moisture_map = generate_moisture_map(&world.settings);
+    }
+}
+
+/// Assigns each tile a biome from its climate
+pub struct BiomeStep;
+
+impl WorldGenStep for BiomeStep {
+    fn name(&self) -> &'static str {
+        "biomes"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        for x in 0..world.width {
+            for y in 0..world.height {
+                let temperature = world.temperature_map.get_value(x as usize, y as usize);
+                let moisture = world.moisture_map.get_value(x as usize, y as usize);
+                let hex_tile = world.tile_mut(x, y);
+                hex_tile.biome = if hex_tile.terrain_type.is_water() {
+                    Biome::Water
+                } else {
+                    Biome::from_climate(temperature, moisture)
+                };
+            }
+        }
+    }
+}
+
+/// Runs rivers downhill along tile edges and turns the basins they end in into lakes
+pub struct RiverStep;
+
+impl WorldGenStep for RiverStep {
+    fn name(&self) -> &'static str {
+        "rivers"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        let river_map = generate_rivers(&world.settings, &world.heightmap, &world.moisture_map, world.sea_level);
+        for x in 0..world.width {
+            for y in 0..world.height {
+                let hex_tile = world.tile_mut(x, y);
+                hex_tile.rivers = river_map.edges(x, y);
+                if river_map.is_lake(x, y) {
+                    hex_tile.terrain_type = TerrainType::Lake;
+                    hex_tile.biome = Biome::Water;
+                }
+            }
+        }
+    }
+}
+
+/// Groups water tiles into oceans, seas and lakes
+pub struct WaterBodyStep;
+
+impl WorldGenStep for WaterBodyStep {
+    fn name(&self) -> &'static str {
+        "water_bodies"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        let bounds = world.bounds();
+        world.water_bodies = detect_water_bodies(&mut world.hex_grid, bounds);
+    }
+}
+
+/// Groups land tiles into continents, islands and islets
+pub struct LandmassStep;
+
+impl WorldGenStep for LandmassStep {
+    fn name(&self) -> &'static str {
+        "landmasses"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        let bounds = world.bounds();
+        world.landmasses = detect_landmasses(&mut world.hex_grid, bounds);
+    }
+}
+
+/// Places natural resource deposits
+pub struct ResourceStep;
+
+impl WorldGenStep for ResourceStep {
+    fn name(&self) -> &'static str {
+        "resources"
+    }
+
+    fn run(&self, world: &mut WorldData) {
+        place_resources(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Records its name when it runs, so tests can see which steps ran and in what order
+    struct RecordStep {
+        name: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl WorldGenStep for RecordStep {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn run(&self, _world: &mut WorldData) {
+            self.log.lock().unwrap().push(self.name);
+        }
+    }
+
+    fn recording_pipeline(names: &[&'static str]) -> (WorldGenPipeline, Arc<Mutex<Vec<&'static str>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut pipeline = WorldGenPipeline::empty();
+        for &name in names {
+            pipeline.add_step(RecordStep { name, log: log.clone() });
+        }
+        (pipeline, log)
+    }
+
+    fn small_world() -> WorldData {
+        WorldData::new(WorldGenSettings {
+            chunks_width: 1,
+            chunks_height: 1,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn insert_step_before() {
+        let (mut pipeline, log) = recording_pipeline(&["a", "b"]);
+        pipeline
+            .insert_step_before("b", RecordStep { name: "c", log: log.clone() })
+            .insert_step_before("missing", RecordStep { name: "d", log });
+        assert_eq!(pipeline.step_names(), vec!["a", "c", "b", "d"]);
+    }
+
+    #[test]
+    fn move_step_before() {
+        let (mut pipeline, _) = recording_pipeline(&["a", "b", "c"]);
+        pipeline.move_step_before("c", "a");
+        assert_eq!(pipeline.step_names(), vec!["c", "a", "b"]);
+        pipeline.move_step_before("c", "missing");
+        assert_eq!(pipeline.step_names(), vec!["a", "b", "c"]);
+        pipeline.move_step_before("missing", "a");
+        assert_eq!(pipeline.step_names(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn remove_step() {
+        let (mut pipeline, _) = recording_pipeline(&["a", "b", "c"]);
+        pipeline.remove_step("b").remove_step("missing");
+        assert_eq!(pipeline.step_names(), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn disabled_steps_are_skipped() {
+        let (mut pipeline, log) = recording_pipeline(&["a", "b", "c"]);
+        pipeline.set_enabled("b", false);
+        pipeline.run_on(&mut small_world());
+        assert_eq!(*log.lock().unwrap(), vec!["a", "c"]);
+        assert_eq!(pipeline.step_names(), vec!["a", "b", "c"]);
+
+        log.lock().unwrap().clear();
+        pipeline.set_enabled("b", true);
+        pipeline.run_on(&mut small_world());
+        assert_eq!(*log.lock().unwrap(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn steps_run_on_bare_world_data() {
+        let mut world = small_world();
+        let mut pipeline = WorldGenPipeline::empty();
+        pipeline.add_step(HeightmapStep).add_step(TerrainStep).add_step(WaterBodyStep);
+        pipeline.run_on(&mut world);
+        for x in 0..world.width {
+            for y in 0..world.height {
+                let height = world.heightmap.get_value(x as usize, y as usize);
+                let hex_tile = world.tile(x, y);
+                // water bodies too small to be seas become lakes, but stay water
+                let expected = TerrainType::from_elevation(height, world.sea_level);
+                assert_eq!(hex_tile.terrain_type.is_water(), expected.is_water());
+                if !expected.is_water() {
+                    assert_eq!(hex_tile.terrain_type, expected);
+                }
+                assert_eq!(hex_tile.water_body.is_some(), hex_tile.terrain_type.is_water());
+            }
+        }
+        // steps that didn't run leave their output empty
+        assert!(world.landmasses.landmasses.is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+//! The presets and steps the new game screen changes `WorldGenSettings` by, and typing in seeds.
+//!
+//! None of this needs the game client, so it is tested without it.
+
+use crate::worldgen::WorldGenSettings;
+
+/// Map size presets in chunks
+pub const MAP_SIZES: [(&str, i32, i32); 4] = [
+    ("Small", 4, 2),
+    ("Medium", 6, 3),
+    ("Large", 8, 4),
+    ("Huge", 12, 6),
+];
+/// Preset that stepping the map size starts from when the size isn't a preset
+const DEFAULT_MAP_SIZE: usize = 1;
+
+pub const SEA_LEVEL_STEP: f64 = 0.05;
+pub const LAND_PERCENTAGE_STEP: f64 = 0.05;
+
+/// Index of the settings' map size in `MAP_SIZES`, or `None` for a custom size
+pub fn map_size_index(settings: &WorldGenSettings) -> Option<usize> {
+    MAP_SIZES
+        .iter()
+        .position(|&(_, width, height)| width == settings.chunks_width && height == settings.chunks_height)
+}
+
+/// Switches to the next bigger or smaller map size preset, stopping at the first and last
+pub fn step_map_size(settings: &mut WorldGenSettings, bigger: bool) {
+    let index = map_size_index(settings).unwrap_or(DEFAULT_MAP_SIZE);
+    let index = if bigger {
+        (index + 1).min(MAP_SIZES.len() - 1)
+    } else {
+        index.saturating_sub(1)
+    };
+    let (_, width, height) = MAP_SIZES[index];
+    settings.chunks_width = width;
+    settings.chunks_height = height;
+}
+
+/// Raises or lowers the sea level, which stops deriving it from the land percentage
+pub fn step_sea_level(settings: &mut WorldGenSettings, up: bool) {
+    settings.land_percentage = None;
+    settings.sea_level = if up {
+        (settings.sea_level + SEA_LEVEL_STEP).min(1.0)
+    } else {
+        (settings.sea_level - SEA_LEVEL_STEP).max(-1.0)
+    };
+}
+
+/// Raises or lowers the land percentage. Lowering it below the first step switches back to
+/// using the sea level.
+pub fn step_land_percentage(settings: &mut WorldGenSettings, up: bool) {
+    settings.land_percentage = match (settings.land_percentage, up) {
+        (Some(land_percentage), true) => Some((land_percentage + LAND_PERCENTAGE_STEP).min(1.0)),
+        (None, true) => Some(LAND_PERCENTAGE_STEP),
+        (Some(land_percentage), false) if land_percentage - LAND_PERCENTAGE_STEP > 0.0 => {
+            Some(land_percentage - LAND_PERCENTAGE_STEP)
+        }
+        (_, false) => None,
+    };
+}
+
+/// Typing over a seed the player didn't type, like a randomized one, starts a new seed
+/// rather than adding digits to the end of it
+#[derive(Debug, Default)]
+pub struct SeedEntry {
+    replace: bool,
+}
+
+impl SeedEntry {
+    /// Makes the next digit start a new seed, after the seed was set some other way
+    pub fn replace_next(&mut self) {
+        self.replace = true;
+    }
+
+    /// The seed with a digit typed after it. Digits that would overflow the seed are ignored.
+    pub fn type_digit(&mut self, seed: u32, digit: u32) -> u32 {
+        let current = if self.replace { 0 } else { seed };
+        self.replace = false;
+        current
+            .checked_mul(10)
+            .and_then(|seed| seed.checked_add(digit))
+            .unwrap_or(seed)
+    }
+
+    /// The seed with its last digit removed
+    pub fn backspace(&mut self, seed: u32) -> u32 {
+        self.replace = false;
+        seed / 10
+    }
+
+    /// An empty seed, for typing one from scratch
+    pub fn clear(&mut self) -> u32 {
+        self.replace = false;
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_digits(seed_entry: &mut SeedEntry, mut seed: u32, digits: &str) -> u32 {
+        for digit in digits.chars() {
+            seed = seed_entry.type_digit(seed, digit.to_digit(10).unwrap());
+        }
+        seed
+    }
+
+    #[test]
+    fn typed_digits_add_to_the_seed() {
+        let mut seed_entry = SeedEntry::default();
+        assert_eq!(type_digits(&mut seed_entry, 12, "34"), 1234);
+        assert_eq!(seed_entry.backspace(1234), 123);
+        assert_eq!(seed_entry.clear(), 0);
+        assert_eq!(type_digits(&mut seed_entry, 0, "0"), 0);
+    }
+
+    #[test]
+    fn typing_replaces_a_seed_that_wasnt_typed() {
+        let mut seed_entry = SeedEntry::default();
+        seed_entry.replace_next();
+        assert_eq!(type_digits(&mut seed_entry, 987_654, "42"), 42);
+        // backspace keeps editing the seed that is there
+        seed_entry.replace_next();
+        assert_eq!(seed_entry.backspace(987_654), 98_765);
+        assert_eq!(type_digits(&mut seed_entry, 98_765, "4"), 987_654);
+    }
+
+    #[test]
+    fn digits_that_would_overflow_are_ignored() {
+        let mut seed_entry = SeedEntry::default();
+        assert_eq!(type_digits(&mut seed_entry, 429_496_729, "5"), u32::MAX);
+        assert_eq!(type_digits(&mut seed_entry, 429_496_729, "6"), 429_496_729);
+        assert_eq!(type_digits(&mut seed_entry, u32::MAX, "0"), u32::MAX);
+    }
+
+    #[test]
+    fn map_size_steps_through_the_presets() {
+        let mut settings = WorldGenSettings { chunks_width: 5, chunks_height: 5, ..Default::default() };
+        assert_eq!(map_size_index(&settings), None);
+        step_map_size(&mut settings, true);
+        assert_eq!(map_size_index(&settings), Some(DEFAULT_MAP_SIZE + 1));
+        for _ in 0..MAP_SIZES.len() {
+            step_map_size(&mut settings, true);
+        }
+        assert_eq!((settings.chunks_width, settings.chunks_height), (12, 6));
+        for _ in 0..MAP_SIZES.len() {
+            step_map_size(&mut settings, false);
+        }
+        assert_eq!((settings.chunks_width, settings.chunks_height), (4, 2));
+    }
+
+    #[test]
+    fn sea_level_steps_replace_the_land_percentage() {
+        let mut settings = WorldGenSettings { sea_level: 0.95, land_percentage: Some(0.3), ..Default::default() };
+        step_sea_level(&mut settings, true);
+        assert_eq!(settings.land_percentage, None);
+        assert!((settings.sea_level - 1.0).abs() < 1e-9);
+        step_sea_level(&mut settings, true);
+        assert_eq!(settings.sea_level, 1.0);
+        step_sea_level(&mut settings, false);
+        assert!((settings.sea_level - 0.95).abs() < 1e-9);
+    }
+
+    #[test]
+    fn land_percentage_steps_turn_it_on_and_off() {
+        let mut settings = WorldGenSettings::default();
+        assert_eq!(settings.land_percentage, None);
+        step_land_percentage(&mut settings, true);
+        assert_eq!(settings.land_percentage, Some(LAND_PERCENTAGE_STEP));
+        step_land_percentage(&mut settings, true);
+        step_land_percentage(&mut settings, false);
+        assert!((settings.land_percentage.unwrap() - LAND_PERCENTAGE_STEP).abs() < 1e-9);
+        step_land_percentage(&mut settings, false);
+        assert_eq!(settings.land_percentage, None);
+        step_land_percentage(&mut settings, false);
+        assert_eq!(settings.land_percentage, None);
+
+        settings.land_percentage = Some(0.98);
+        step_land_percentage(&mut settings, true);
+        assert_eq!(settings.land_percentage, Some(1.0));
+    }
+}
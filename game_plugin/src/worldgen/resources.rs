@@ -0,0 +1,139 @@
+use crate::{hex::HexCoord, worldgen::{WorldData, build_sphere_map, climate::Biome, terrain::TerrainType, world_noise}};
+use noise::MultiFractal;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const RESOURCE_SEED_OFFSET: u64 = 6;
+/// Each resource gets its own clustering noise, seeded from this offset plus its index
+const RESOURCE_NOISE_SEED_OFFSET: u32 = 10;
+/// Clustering noise is higher frequency than the heightmap, so deposits form small fields
+const CLUSTER_FREQUENCY: f64 = 4.0;
+const CLUSTER_OCTAVES: usize = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ResourceType {
+    Iron,
+    Copper,
+    Gold,
+    Stone,
+    Timber,
+    Fish,
+    FertileSoil,
+    Horses,
+    Salt,
+}
+
+/// How a resource is distributed over the tiles it can occur on
+struct ResourceRule {
+    /// Clustering noise must be above this for a deposit, so higher values make rarer clusters
+    cluster_threshold: f64,
+    /// Chance that an eligible tile inside a cluster gets a deposit
+    density: f64,
+    /// Quantity of a deposit at the edge of a cluster, growing towards its center
+    base_quantity: u32,
+}
+
+impl ResourceType {
+    /// In placement order: when several resources could go on a tile, the earliest wins
+    pub const ALL: [ResourceType; 9] = [
+        ResourceType::Gold,
+        ResourceType::Copper,
+        ResourceType::Iron,
+        ResourceType::Salt,
+        ResourceType::Horses,
+        ResourceType::Fish,
+        ResourceType::FertileSoil,
+        ResourceType::Timber,
+        ResourceType::Stone,
+    ];
+
+    fn rule(self) -> ResourceRule {
+        let (cluster_threshold, density, base_quantity) = match self {
+            ResourceType::Gold => (0.35, 0.3, 20),
+            ResourceType::Copper => (0.25, 0.4, 50),
+            ResourceType::Iron => (0.2, 0.4, 60),
+            ResourceType::Salt => (0.25, 0.4, 40),
+            ResourceType::Horses => (0.2, 0.3, 30),
+            ResourceType::Fish => (0.1, 0.3, 80),
+            ResourceType::FertileSoil => (0.0, 0.5, 100),
+            ResourceType::Timber => (0.0, 0.4, 100),
+            ResourceType::Stone => (0.1, 0.3, 120),
+        };
+        ResourceRule { cluster_threshold, density, base_quantity }
+    }
+
+    /// Whether the terrain, biome and rivers of a tile allow this resource
+    pub fn can_occur_on(self, world: &WorldData, coord: HexCoord) -> bool {
+        let hex_tile = world.tile(coord.x, coord.y);
+        let terrain = hex_tile.terrain_type;
+        let biome = hex_tile.biome;
+        match self {
+            ResourceType::Gold | ResourceType::Copper | ResourceType::Iron => {
+                matches!(terrain, TerrainType::Hills | TerrainType::Mountains)
+            }
+            ResourceType::Stone => {
+                matches!(terrain, TerrainType::Hills | TerrainType::Mountains | TerrainType::SnowPeaks)
+            }
+            ResourceType::Salt => {
+                terrain == TerrainType::Beach || (biome == Biome::Desert && terrain == TerrainType::Plains)
+            }
+            ResourceType::Horses => {
+                terrain == TerrainType::Plains && matches!(biome, Biome::Grassland | Biome::Savanna | Biome::Tundra)
+            }
+            ResourceType::Fish => matches!(terrain, TerrainType::Coast | TerrainType::Lake),
+            ResourceType::FertileSoil => {
+                let has_river = world.has_river(coord);
+                terrain == TerrainType::Plains
+                    && (has_river || matches!(biome, Biome::Grassland | Biome::TemperateForest | Biome::Savanna))
+            }
+            ResourceType::Timber => {
+                matches!(terrain, TerrainType::Plains | TerrainType::Hills) && biome.is_forest()
+            }
+        }
+    }
+}
+
+/// A resource deposit on a tile. Quantity is how much can be extracted before it runs out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResourceDeposit {
+    pub resource: ResourceType,
+    pub quantity: u32,
+}
+
+/// Places resource deposits on tiles suited to them.
+/// Each resource is clustered by its own noise layer, so deposits come in fields and veins
+/// instead of being scattered evenly. Tiles have at most one deposit.
+pub fn place_resources(world: &mut WorldData) {
+    let mut rng = StdRng::seed_from_u64(world.settings.seed as u64 + RESOURCE_SEED_OFFSET);
+    let cluster_maps: Vec<_> = ResourceType::ALL
+        .iter()
+        .enumerate()
+        .map(|(index, _)| {
+            let noise = world_noise(&world.settings, RESOURCE_NOISE_SEED_OFFSET + index as u32)
+                .set_octaves(CLUSTER_OCTAVES)
+                .set_frequency(world.settings.noise_frequency * CLUSTER_FREQUENCY);
+            build_sphere_map(&world.settings, &noise)
+        })
+        .collect();
+
+    for y in 0..world.height {
+        for x in 0..world.width {
+            let mut deposit = None;
+            for (index, &resource) in ResourceType::ALL.iter().enumerate() {
+                if !resource.can_occur_on(world, HexCoord::new(x, y)) {
+                    continue;
+                }
+                let rule = resource.rule();
+                let cluster = cluster_maps[index].get_value(x as usize, y as usize);
+                if cluster > rule.cluster_threshold && rng.gen_bool(rule.density) {
+                    let richness = 1.0 + (cluster - rule.cluster_threshold) * 4.0;
+                    deposit = Some(ResourceDeposit {
+                        resource,
+                        quantity: (rule.base_quantity as f64 * richness).round() as u32,
+                    });
+                    break;
+                }
+            }
+            world.tile_mut(x, y).resource = deposit;
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{hex::{HexDirection, MapBounds}, worldgen::WorldGenSettings};
+use noise::utils::NoiseMap;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const RIVER_SEED_OFFSET: u64 = 3;
+/// Minimum height above sea level for a tile to be a river source
+const SOURCE_MIN_ELEVATION: f64 = 0.15;
+/// Minimum moisture for a tile to be a river source
+const SOURCE_MIN_MOISTURE: f64 = 0.55;
+/// Chance that an eligible tile becomes a river source
+const SOURCE_CHANCE: f64 = 0.05;
+/// Each tile stores the flow along its north, north-east and south-east edges.
+/// Its other edges are stored by the neighbors on those sides.
+pub const OWNED_EDGES: usize = 3;
+
+/// The tile storing the river flow along an edge of a tile, and the index of the edge in its
+/// `rivers`, or `None` for edges on the border of the map, which never have rivers
+pub fn edge_owner(bounds: MapBounds, x: i32, y: i32, direction: HexDirection) -> Option<((i32, i32), usize)> {
+    let neighbor = bounds.neighbor(x, y, direction)?;
+    if (direction as usize) < OWNED_EDGES {
+        Some(((x, y), direction as usize))
+    } else {
+        Some((neighbor, direction.opposite() as usize))
+    }
+}
+
+/// Rivers stored as flow along tile edges, plus the lakes rivers drain into
+pub struct RiverMap {
+    bounds: MapBounds,
+    /// Flow along the edges each tile owns, see `edge_owner`
+    edges: Vec<[u32; OWNED_EDGES]>,
+    /// Whether each tile is a lake, in the same layout as `edges`
+    lake_tiles: Vec<bool>,
+    lakes: Vec<(i32, i32)>,
+}
+
+impl RiverMap {
+    fn new(bounds: MapBounds) -> Self {
+        let tile_count = (bounds.width * bounds.height) as usize;
+        RiverMap {
+            bounds,
+            edges: vec![[0; OWNED_EDGES]; tile_count],
+            lake_tiles: vec![false; tile_count],
+            lakes: Vec::new(),
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (x + y * self.bounds.width) as usize
+    }
+
+    /// River flow along the edges a tile owns, indexed by `HexDirection`
+    pub fn edges(&self, x: i32, y: i32) -> [u32; OWNED_EDGES] {
+        self.edges[self.index(x, y)]
+    }
+
+    /// River flow along any edge of a tile
+    pub fn flow(&self, x: i32, y: i32, direction: HexDirection) -> u32 {
+        match edge_owner(self.bounds, x, y, direction) {
+            Some(((x, y), edge)) => self.edges[self.index(x, y)][edge],
+            None => 0,
+        }
+    }
+
+    /// Whether rivers drain into a lake at this tile
+    pub fn is_lake(&self, x: i32, y: i32) -> bool {
+        self.lake_tiles[self.index(x, y)]
+    }
+
+    pub fn lakes(&self) -> &[(i32, i32)] {
+        &self.lakes
+    }
+
+    fn add_lake(&mut self, (x, y): (i32, i32)) {
+        let index = self.index(x, y);
+        self.lake_tiles[index] = true;
+        self.lakes.push((x, y));
+    }
+
+    fn add_flow(&mut self, (x, y): (i32, i32), direction: HexDirection) {
+        if let Some(((x, y), edge)) = edge_owner(self.bounds, x, y, direction) {
+            let index = self.index(x, y);
+            self.edges[index][edge] += 1;
+        }
+    }
+}
+
+/// Corner where a tile meets its neighbors in `direction` and the next direction clockwise.
+/// Rivers run from corner to corner along the edges between tiles.
+#[derive(Debug, Copy, Clone)]
+struct Vertex {
+    tile: (i32, i32),
+    direction: usize,
+}
+
+impl Vertex {
+    /// The three tiles meeting at the corner, `None` where they are off the map
+    fn tiles(self, bounds: MapBounds) -> [Option<(i32, i32)>; 3] {
+        let (x, y) = self.tile;
+        [
+            Some(self.tile),
+            bounds.neighbor(x, y, HexDirection::ALL[self.direction]),
+            bounds.neighbor(x, y, HexDirection::ALL[(self.direction + 1) % 6]),
+        ]
+    }
+
+    /// Mean height of the tiles on the map
+    fn height(self, heightmap: &NoiseMap, bounds: MapBounds) -> f64 {
+        let heights: Vec<f64> = self
+            .tiles(bounds)
+            .iter()
+            .flatten()
+            .map(|&(x, y)| heightmap.get_value(x as usize, y as usize))
+            .collect();
+        heights.iter().sum::<f64>() / heights.len() as f64
+    }
+
+    /// The corners at the other end of the three edges meeting here, with the tile and
+    /// direction of each edge. Edges along the border of the map are left out.
+    fn next(self, bounds: MapBounds) -> Vec<(Vertex, (i32, i32), HexDirection)> {
+        let [_, first, second] = self.tiles(bounds);
+        let direction = self.direction;
+        let mut next = Vec::with_capacity(3);
+        if first.is_some() {
+            // between the tile and its first neighbor, to the corner with the previous neighbor
+            let vertex = Vertex { tile: self.tile, direction: (direction + 5) % 6 };
+            next.push((vertex, self.tile, HexDirection::ALL[direction]));
+        }
+        if second.is_some() {
+            // between the tile and its second neighbor, to the corner with the neighbor after it
+            let vertex = Vertex { tile: self.tile, direction: (direction + 1) % 6 };
+            next.push((vertex, self.tile, HexDirection::ALL[(direction + 1) % 6]));
+        }
+        if let (Some(first), Some(_)) = (first, second) {
+            // between the two neighbors, away from the tile. Seen from the first neighbor the
+            // second is two directions further clockwise.
+            let vertex = Vertex { tile: first, direction: (direction + 1) % 6 };
+            next.push((vertex, first, HexDirection::ALL[(direction + 2) % 6]));
+        }
+        next
+    }
+}
+
+/// Finds the lowest corner one edge away that is lower than the corner, if any
+fn downhill(heightmap: &NoiseMap, vertex: Vertex, bounds: MapBounds) -> Option<(Vertex, (i32, i32), HexDirection)> {
+    let mut lowest = vertex.height(heightmap, bounds);
+    let mut result = None;
+    for (next, tile, direction) in vertex.next(bounds) {
+        let next_height = next.height(heightmap, bounds);
+        if next_height < lowest {
+            lowest = next_height;
+            result = Some((next, tile, direction));
+        }
+    }
+    result
+}
+
+/// Traces rivers downhill along tile edges from high, wet tiles until they reach water.
+/// Rivers that meet follow the same path from then on, so their flow adds up.
+/// Rivers stuck in a local minimum end in a lake on the lowest tile there.
+pub fn generate_rivers(
+    settings: &WorldGenSettings,
+    heightmap: &NoiseMap,
+    moisture_map: &NoiseMap,
+    sea_level: f64,
+) -> RiverMap {
+    let bounds = settings.map_bounds();
+    let (width, height) = (bounds.width, bounds.height);
+    let mut river_map = RiverMap::new(bounds);
+    let mut rng = StdRng::seed_from_u64(settings.seed as u64 + RIVER_SEED_OFFSET);
+
+    let mut sources: Vec<(i32, i32)> = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let elevation = heightmap.get_value(x as usize, y as usize) - sea_level;
+            let moisture = moisture_map.get_value(x as usize, y as usize);
+            if elevation >= SOURCE_MIN_ELEVATION
+                && moisture >= SOURCE_MIN_MOISTURE
+                && rng.gen_bool(SOURCE_CHANCE) {
+                sources.push((x, y));
+            }
+        }
+    }
+
+    let tile_height = |(x, y): (i32, i32)| heightmap.get_value(x as usize, y as usize);
+    for source in sources {
+        // rivers leave the source tile from its lowest corner
+        let mut current = (0..6)
+            .map(|direction| Vertex { tile: source, direction })
+            .min_by(|a, b| a.height(heightmap, bounds).partial_cmp(&b.height(heightmap, bounds)).unwrap())
+            .unwrap();
+        loop {
+            let tiles: Vec<(i32, i32)> = current.tiles(bounds).iter().flatten().copied().collect();
+            let reached_water = tiles
+                .iter()
+                .any(|&(x, y)| tile_height((x, y)) < sea_level || river_map.is_lake(x, y));
+            if reached_water {
+                break;
+            }
+            match downhill(heightmap, current, bounds) {
+                Some((next, tile, direction)) => {
+                    river_map.add_flow(tile, direction);
+                    current = next;
+                }
+                None => {
+                    let lowest = tiles
+                        .into_iter()
+                        .min_by(|&a, &b| tile_height(a).partial_cmp(&tile_height(b)).unwrap())
+                        .unwrap();
+                    river_map.add_lake(lowest);
+                    break;
+                }
+            }
+        }
+    }
+
+    river_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Land sloping down to a strip of sea along the west edge, wet enough for rivers everywhere
+    fn western_slope() -> (WorldGenSettings, NoiseMap, NoiseMap) {
+        let settings = WorldGenSettings { chunks_width: 1, chunks_height: 1, sea_level: 0.0, ..Default::default() };
+        let (width, height) = (settings.map_width() as usize, settings.map_height() as usize);
+        let mut heightmap = NoiseMap::new(width, height);
+        let mut moisture_map = NoiseMap::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                heightmap.set_value(x, y, (x as f64 - 4.0) * 0.01);
+                moisture_map.set_value(x, y, 1.0);
+            }
+        }
+        (settings, heightmap, moisture_map)
+    }
+
+    #[test]
+    fn both_sides_of_an_edge_see_the_same_flow() {
+        let (settings, heightmap, moisture_map) = western_slope();
+        let river_map = generate_rivers(&settings, &heightmap, &moisture_map, settings.sea_level);
+        let bounds = settings.map_bounds();
+        let mut river_edges = 0;
+        for y in 0..bounds.height {
+            for x in 0..bounds.width {
+                for &direction in HexDirection::ALL.iter() {
+                    let flow = river_map.flow(x, y, direction);
+                    match bounds.neighbor(x, y, direction) {
+                        Some((nx, ny)) => assert_eq!(river_map.flow(nx, ny, direction.opposite()), flow),
+                        None => assert_eq!(flow, 0),
+                    }
+                    if flow > 0 {
+                        river_edges += 1;
+                    }
+                }
+            }
+        }
+        assert!(river_edges > 0);
+    }
+
+    #[test]
+    fn rivers_run_down_to_the_sea() {
+        let (settings, heightmap, moisture_map) = western_slope();
+        let river_map = generate_rivers(&settings, &heightmap, &moisture_map, settings.sea_level);
+        // rivers end on the last land column, running along the edges of the sea tiles
+        let bounds = settings.map_bounds();
+        let mouths = (0..bounds.height)
+            .filter(|&y| HexDirection::ALL.iter().any(|&direction| river_map.flow(4, y, direction) > 0))
+            .count();
+        assert!(mouths > 0);
+        assert!((0..bounds.height).all(|y| (0..4).all(|x| river_map.edges(x, y) == [0; OWNED_EDGES])));
+    }
+}
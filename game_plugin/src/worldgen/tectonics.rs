@@ -0,0 +1,172 @@
+use crate::{hex::{HexDirection, MapBounds}, worldgen::WorldGenSettings};
+use noise::utils::NoiseMap;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::collections::VecDeque;
+
+const TECTONICS_SEED_OFFSET: u64 = 5;
+/// Fraction of plates that are continental rather than oceanic
+const CONTINENTAL_CHANCE: f64 = 0.4;
+/// Height added to continental plates and removed from oceanic ones, relative to the uplift
+const PLATE_BASE_ELEVATION: f64 = 0.25;
+/// How many tiles away from a boundary mountains and rifts reach
+const BOUNDARY_WIDTH: u32 = 6;
+/// Rifts are shallower than mountain ranges are high
+const RIFT_DEPTH: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub struct Plate {
+    pub id: u32,
+    /// Drift direction and speed in world space
+    pub drift: (f64, f64),
+    pub continental: bool,
+}
+
+/// The tectonic plate each tile belongs to
+pub struct PlateMap {
+    width: i32,
+    pub plates: Vec<Plate>,
+    tile_plates: Vec<u32>,
+}
+
+impl PlateMap {
+    pub fn plate_at(&self, x: i32, y: i32) -> &Plate {
+        &self.plates[self.tile_plates[(x + y * self.width) as usize] as usize]
+    }
+}
+
+/// Unit vector pointing across an edge of a flat-topped hex, in world space
+fn direction_vector(direction: HexDirection) -> (f64, f64) {
+    let diagonal_x = 30f64.to_radians().cos();
+    match direction {
+        HexDirection::North => (0.0, 1.0),
+        HexDirection::NorthEast => (diagonal_x, 0.5),
+        HexDirection::SouthEast => (diagonal_x, -0.5),
+        HexDirection::South => (0.0, -1.0),
+        HexDirection::SouthWest => (-diagonal_x, -0.5),
+        HexDirection::NorthWest => (-diagonal_x, 0.5),
+    }
+}
+
+/// Splits the map into plates by growing them outwards from random seed tiles at the same rate
+fn partition_plates(rng: &mut StdRng, plate_count: u32, bounds: MapBounds) -> Vec<u32> {
+    let (width, height) = (bounds.width, bounds.height);
+    let mut tile_plates: Vec<Option<u32>> = vec![None; (width * height) as usize];
+    let mut queue = VecDeque::new();
+    for id in 0..plate_count {
+        let x = rng.gen_range(0..width);
+        let y = rng.gen_range(0..height);
+        let index = (x + y * width) as usize;
+        if tile_plates[index].is_none() {
+            tile_plates[index] = Some(id);
+            queue.push_back((x, y));
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        let plate = tile_plates[(x + y * width) as usize];
+        for &direction in HexDirection::ALL.iter() {
+            if let Some((nx, ny)) = bounds.neighbor(x, y, direction) {
+                let index = (nx + ny * width) as usize;
+                if tile_plates[index].is_none() {
+                    tile_plates[index] = plate;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+    }
+    tile_plates.into_iter().map(|plate| plate.unwrap_or(0)).collect()
+}
+
+/// How strongly two neighboring plates collide across an edge.
+/// Positive values are convergent, negative values divergent.
+fn boundary_stress(plate: &Plate, other: &Plate, direction: HexDirection) -> f64 {
+    let (dx, dy) = direction_vector(direction);
+    let relative_x = plate.drift.0 - other.drift.0;
+    let relative_y = plate.drift.1 - other.drift.1;
+    relative_x * dx + relative_y * dy
+}
+
+/// Partitions the map into drifting plates and blends their effect into the heightmap:
+/// continental plates are raised, oceanic plates lowered, mountain ranges pushed up where
+/// plates converge and rifts opened where they pull apart.
+pub fn apply_plate_tectonics(settings: &WorldGenSettings, heightmap: &mut NoiseMap) -> Option<PlateMap> {
+    if settings.plate_count == 0 {
+        return None;
+    }
+    let bounds = settings.map_bounds();
+    let (width, height) = (bounds.width, bounds.height);
+    let mut rng = StdRng::seed_from_u64(settings.seed as u64 + TECTONICS_SEED_OFFSET);
+
+    let plates: Vec<Plate> = (0..settings.plate_count)
+        .map(|id| {
+            let angle = rng.gen_range(0.0..std::f64::consts::PI * 2.0);
+            let speed = rng.gen_range(0.2..1.0);
+            Plate {
+                id,
+                drift: (angle.cos() * speed, angle.sin() * speed),
+                continental: rng.gen_bool(CONTINENTAL_CHANCE),
+            }
+        })
+        .collect();
+    let tile_plates = partition_plates(&mut rng, settings.plate_count, bounds);
+    let plate_map = PlateMap { width, plates, tile_plates };
+
+    // find boundary tiles and spread their stress outwards, fading with distance
+    let mut stress: Vec<Option<(f64, u32)>> = vec![None; (width * height) as usize];
+    let mut queue = VecDeque::new();
+    for y in 0..height {
+        for x in 0..width {
+            let plate = plate_map.plate_at(x, y);
+            let mut strongest: Option<f64> = None;
+            for &direction in HexDirection::ALL.iter() {
+                if let Some((nx, ny)) = bounds.neighbor(x, y, direction) {
+                    let other = plate_map.plate_at(nx, ny);
+                    if other.id != plate.id {
+                        let edge_stress = boundary_stress(plate, other, direction);
+                        match strongest {
+                            Some(current) if current.abs() >= edge_stress.abs() => {}
+                            _ => strongest = Some(edge_stress),
+                        }
+                    }
+                }
+            }
+            if let Some(edge_stress) = strongest {
+                stress[(x + y * width) as usize] = Some((edge_stress, 0));
+                queue.push_back((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        let (edge_stress, distance) = stress[(x + y * width) as usize].unwrap();
+        if distance + 1 >= BOUNDARY_WIDTH {
+            continue;
+        }
+        for &direction in HexDirection::ALL.iter() {
+            if let Some((nx, ny)) = bounds.neighbor(x, y, direction) {
+                let index = (nx + ny * width) as usize;
+                if stress[index].is_none() {
+                    stress[index] = Some((edge_stress, distance + 1));
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+    }
+
+    for y in 0..height {
+        for x in 0..width {
+            let plate = plate_map.plate_at(x, y);
+            let base = if plate.continental { PLATE_BASE_ELEVATION } else { -PLATE_BASE_ELEVATION };
+            let boundary = match stress[(x + y * width) as usize] {
+                Some((edge_stress, distance)) => {
+                    let falloff = 1.0 - distance as f64 / BOUNDARY_WIDTH as f64;
+                    let edge_stress = if edge_stress < 0.0 { edge_stress * RIFT_DEPTH } else { edge_stress };
+                    edge_stress * falloff
+                }
+                None => 0.0,
+            };
+            let value = heightmap.get_value(x as usize, y as usize);
+            heightmap.set_value(x as usize, y as usize, value + (base + boundary) * settings.plate_uplift);
+        }
+    }
+
+    Some(plate_map)
+}
@@ -0,0 +1,78 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TerrainType {
+    DeepOcean,
+    Coast,
+    Beach,
+    Plains,
+    Hills,
+    Mountains,
+    SnowPeaks,
+    Lake,
+}
+
+/// Index of the forest tile in `textures/tileset.png`, drawn over forested land instead of its terrain
+pub const FOREST_TILESET_INDEX: u32 = 3;
+
+/// Upper bound of each terrain band, as an offset from sea level.
+/// Anything above the last band is `SnowPeaks`.
+const ELEVATION_BANDS: [(f64, TerrainType); 6] = [
+    (-0.15, TerrainType::DeepOcean),
+    (0.0, TerrainType::Coast),
+    (0.02, TerrainType::Beach),
+    (0.12, TerrainType::Plains),
+    (0.22, TerrainType::Hills),
+    (0.32, TerrainType::Mountains),
+];
+
+impl TerrainType {
+    /// Picks the terrain for a heightmap value
+    pub fn from_elevation(height: f64, sea_level: f64) -> TerrainType {
+        for &(upper_bound, terrain_type) in ELEVATION_BANDS.iter() {
+            if height < sea_level + upper_bound {
+                return terrain_type;
+            }
+        }
+        TerrainType::SnowPeaks
+    }
+
+    pub fn is_water(&self) -> bool {
+        matches!(self, TerrainType::DeepOcean | TerrainType::Coast | TerrainType::Lake)
+    }
+
+    /// Index of this terrain's tile in `textures/tileset.png`. Every terrain has its own tile.
+    pub fn tileset_index(&self) -> u32 {
+        match self {
+            TerrainType::DeepOcean => 0,
+            TerrainType::Coast => 1,
+            TerrainType::Beach => 4,
+            TerrainType::Plains => 2,
+            TerrainType::Hills => 5,
+            TerrainType::Mountains => 7,
+            TerrainType::SnowPeaks => 6,
+            TerrainType::Lake => 8,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn terrains_have_distinct_tiles() {
+        let terrain_types = [
+            TerrainType::DeepOcean,
+            TerrainType::Coast,
+            TerrainType::Beach,
+            TerrainType::Plains,
+            TerrainType::Hills,
+            TerrainType::Mountains,
+            TerrainType::SnowPeaks,
+            TerrainType::Lake,
+        ];
+        let mut tiles: HashSet<u32> = terrain_types.iter().map(|terrain_type| terrain_type.tileset_index()).collect();
+        assert_eq!(tiles.len(), terrain_types.len());
+        assert!(tiles.insert(FOREST_TILESET_INDEX));
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{hex::{HexCoord, HexDirection, MapBounds}, worldgen::{HexTile, terrain::TerrainType}};
+use chickenwire::prelude::HexGrid;
+use std::collections::VecDeque;
+
+/// Water bodies at least this many tiles are seas, or oceans when connected to the map edge.
+/// Smaller ones are lakes, even on the map edge.
+const SEA_MIN_SIZE: usize = 250;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WaterBodyId(pub u32);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WaterBodyKind {
+    /// Large body of water connected to the edge of the map
+    Ocean,
+    /// Large enclosed body of water
+    Sea,
+    /// Small enclosed body of water
+    Lake,
+}
+
+#[derive(Debug, Clone)]
+pub struct WaterBody {
+    pub id: WaterBodyId,
+    pub kind: WaterBodyKind,
+    pub size: usize,
+}
+
+/// All water bodies on the map, indexed by `WaterBodyId`
+#[derive(Debug, Clone, Default)]
+pub struct WaterBodies {
+    pub bodies: Vec<WaterBody>,
+}
+
+impl WaterBodies {
+    pub fn get(&self, id: WaterBodyId) -> &WaterBody {
+        &self.bodies[id.0 as usize]
+    }
+}
+
+fn is_water(hex_grid: &HexGrid<HexTile>, x: i32, y: i32) -> bool {
+    hex_grid.get(HexCoord::new(x, y).into()).unwrap().terrain_type.is_water()
+}
+
+/// Flood fills the water body containing a tile, returning its tiles
+fn flood_fill(
+    hex_grid: &HexGrid<HexTile>,
+    visited: &mut [bool],
+    start: (i32, i32),
+    bounds: MapBounds,
+) -> Vec<(i32, i32)> {
+    let width = bounds.width;
+    let mut tiles = Vec::new();
+    let mut queue = VecDeque::new();
+    visited[(start.0 + start.1 * width) as usize] = true;
+    queue.push_back(start);
+    while let Some((x, y)) = queue.pop_front() {
+        tiles.push((x, y));
+        for &direction in HexDirection::ALL.iter() {
+            if let Some((nx, ny)) = bounds.neighbor(x, y, direction) {
+                let index = (nx + ny * width) as usize;
+                if !visited[index] && is_water(hex_grid, nx, ny) {
+                    visited[index] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+    }
+    tiles
+}
+
+/// Labels every water tile with the water body it belongs to.
+///
+/// Large water bodies reachable from the map edge are ocean and large enclosed ones are seas.
+/// Small ones are lakes wherever they are, so a pond in a basin on the map edge doesn't become
+/// ocean. Lake tiles get the `Lake` terrain.
+pub fn detect_water_bodies(hex_grid: &mut HexGrid<HexTile>, bounds: MapBounds) -> WaterBodies {
+    let (width, height) = (bounds.width, bounds.height);
+    let mut visited = vec![false; (width * height) as usize];
+    let mut water_bodies = WaterBodies::default();
+
+    // edge tiles come first so everything connected to them is found as ocean.
+    // When the map wraps, the east and west edges are not really edges.
+    let side_edge_height = if bounds.wrap_horizontal { 0 } else { height };
+    let edge_tiles = (0..width)
+        .flat_map(|x| vec![(x, 0), (x, height - 1)])
+        .chain((0..side_edge_height).flat_map(|y| vec![(0, y), (width - 1, y)]))
+        .map(|tile| (tile, true));
+    let all_tiles = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|tile| (tile, false));
+
+    for ((x, y), on_edge) in edge_tiles.chain(all_tiles) {
+        if visited[(x + y * width) as usize] || !is_water(hex_grid, x, y) {
+            continue;
+        }
+        let tiles = flood_fill(hex_grid, &mut visited, (x, y), bounds);
+        let kind = if tiles.len() < SEA_MIN_SIZE {
+            WaterBodyKind::Lake
+        } else if on_edge {
+            WaterBodyKind::Ocean
+        } else {
+            WaterBodyKind::Sea
+        };
+        let id = WaterBodyId(water_bodies.bodies.len() as u32);
+        for &(tx, ty) in tiles.iter() {
+            let hex_tile = hex_grid.get_mut(HexCoord::new(tx, ty).into()).unwrap();
+            hex_tile.water_body = Some(id);
+            if kind == WaterBodyKind::Lake {
+                hex_tile.terrain_type = TerrainType::Lake;
+            }
+        }
+        water_bodies.bodies.push(WaterBody {
+            id,
+            kind,
+            size: tiles.len(),
+        });
+    }
+
+    water_bodies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worldgen::{WorldData, WorldGenSettings};
+
+    fn land_world(wrap_horizontal: bool) -> WorldData {
+        let mut world = WorldData::new(WorldGenSettings {
+            chunks_width: 1,
+            chunks_height: 1,
+            wrap_horizontal,
+            ..Default::default()
+        });
+        for y in 0..world.height {
+            for x in 0..world.width {
+                world.tile_mut(x, y).terrain_type = TerrainType::Plains;
+            }
+        }
+        world
+    }
+
+    fn kind_at(world: &WorldData, water_bodies: &WaterBodies, x: i32, y: i32) -> WaterBodyKind {
+        water_bodies.get(world.tile(x, y).water_body.unwrap()).kind
+    }
+
+    #[test]
+    fn small_water_on_the_map_edge_is_a_lake() {
+        let mut world = land_world(false);
+        world.tile_mut(0, 10).terrain_type = TerrainType::Coast;
+        world.tile_mut(20, 0).terrain_type = TerrainType::Coast;
+        world.tile_mut(21, 0).terrain_type = TerrainType::Coast;
+        world.tile_mut(10, 10).terrain_type = TerrainType::Coast;
+        let bounds = world.bounds();
+        let water_bodies = detect_water_bodies(&mut world.hex_grid, bounds);
+        assert_eq!(water_bodies.bodies.len(), 3);
+        assert_eq!(kind_at(&world, &water_bodies, 0, 10), WaterBodyKind::Lake);
+        assert_eq!(world.tile(0, 10).terrain_type, TerrainType::Lake);
+        assert_eq!(kind_at(&world, &water_bodies, 21, 0), WaterBodyKind::Lake);
+        assert_eq!(kind_at(&world, &water_bodies, 10, 10), WaterBodyKind::Lake);
+    }
+
+    #[test]
+    fn large_water_on_the_map_edge_is_ocean() {
+        let mut world = land_world(false);
+        for y in 0..world.height {
+            for x in 0..4 {
+                world.tile_mut(x, y).terrain_type = TerrainType::Coast;
+            }
+        }
+        let bounds = world.bounds();
+        let water_bodies = detect_water_bodies(&mut world.hex_grid, bounds);
+        assert_eq!(water_bodies.bodies.len(), 1);
+        assert_eq!(kind_at(&world, &water_bodies, 3, 10), WaterBodyKind::Ocean);
+        assert_eq!(world.tile(3, 10).terrain_type, TerrainType::Coast);
+    }
+
+    #[test]
+    fn wrapped_maps_have_no_side_edges() {
+        let mut world = land_world(true);
+        world.tile_mut(0, 10).terrain_type = TerrainType::Coast;
+        let bounds = world.bounds();
+        let water_bodies = detect_water_bodies(&mut world.hex_grid, bounds);
+        assert_eq!(kind_at(&world, &water_bodies, 0, 10), WaterBodyKind::Lake);
+    }
+
+    #[test]
+    fn large_enclosed_water_is_a_sea() {
+        let mut world = land_world(false);
+        let sea: Vec<_> = world.bounds().range(HexCoord::new(32, 32), 10).collect();
+        assert!(sea.len() >= SEA_MIN_SIZE);
+        for tile in sea {
+            world.tile_mut(tile.x, tile.y).terrain_type = TerrainType::Coast;
+        }
+        let bounds = world.bounds();
+        let water_bodies = detect_water_bodies(&mut world.hex_grid, bounds);
+        assert_eq!(kind_at(&world, &water_bodies, 32, 32), WaterBodyKind::Sea);
+    }
+}
@@ -0,0 +1,162 @@
+//! Guards against world generation changes silently altering the maps players share seeds for.
+//!
+//! Each case generates a world from a fixed seed and compares a summary against the committed
+//! snapshot in `tests/snapshots`. After an intentional change to world generation, regenerate
+//! the snapshots with `UPDATE_SNAPSHOTS=1 cargo test -p game_plugin --test worldgen_snapshots`.
+//! That fails instead when `CI` is set, so CI can't pass by rewriting the snapshots.
+
+use game_plugin::{hex::HexCoord, worldgen::{WorldData, WorldGenSettings, generate_world}};
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+/// FNV-1a, used instead of `DefaultHasher` because its output is stable across Rust versions
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+fn summarize(world: &WorldData) -> String {
+    let mut terrain_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut biome_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut resource_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut land_tiles = 0;
+    let mut river_tiles = 0;
+    let mut tile_hash = Fnv1a::new();
+    let mut height_hash = Fnv1a::new();
+
+    for y in 0..world.height {
+        for x in 0..world.width {
+            let hex_tile = world.tile(x, y);
+            let terrain = format!("{:?}", hex_tile.terrain_type);
+            let biome = format!("{:?}", hex_tile.biome);
+            tile_hash.write(terrain.as_bytes());
+            tile_hash.write(biome.as_bytes());
+            for flow in hex_tile.rivers.iter() {
+                tile_hash.write(&flow.to_le_bytes());
+            }
+            if let Some(deposit) = hex_tile.resource {
+                let resource = format!("{:?}", deposit.resource);
+                tile_hash.write(resource.as_bytes());
+                tile_hash.write(&deposit.quantity.to_le_bytes());
+                *resource_counts.entry(resource).or_insert(0) += 1;
+            }
+            *terrain_counts.entry(terrain).or_insert(0) += 1;
+            *biome_counts.entry(biome).or_insert(0) += 1;
+            if !hex_tile.terrain_type.is_water() {
+                land_tiles += 1;
+            }
+            if world.has_river(HexCoord::new(x, y)) {
+                river_tiles += 1;
+            }
+            // quantized so the hash doesn't depend on the last bits of floating point results
+            let height = (world.heightmap.get_value(x as usize, y as usize) * 1e6).round() as i64;
+            height_hash.write(&height.to_le_bytes());
+        }
+    }
+
+    let format_counts = |counts: &BTreeMap<String, usize>| {
+        counts
+            .iter()
+            .map(|(name, count)| format!("  {}: {}\n", name, count))
+            .collect::<String>()
+    };
+    format!(
+        "seed: {}\nsize: {}x{}\nsea_level: {:.6}\nland_ratio: {:.4}\nlandmasses: {}\nwater_bodies: {}\nriver_tiles: {}\nterrain:\n{}biomes:\n{}resources:\n{}tile_hash: {:016x}\nheight_hash: {:016x}\n",
+        world.settings.seed,
+        world.width,
+        world.height,
+        world.sea_level,
+        land_tiles as f64 / (world.width * world.height) as f64,
+        world.landmasses.landmasses.len(),
+        world.water_bodies.bodies.len(),
+        river_tiles,
+        format_counts(&terrain_counts),
+        format_counts(&biome_counts),
+        format_counts(&resource_counts),
+        tile_hash.0,
+        height_hash.0,
+    )
+}
+
+fn assert_snapshot(name: &str, settings: WorldGenSettings) {
+    let summary = summarize(&generate_world(settings));
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots", &format!("{}.txt", name)]
+        .iter()
+        .collect();
+
+    if env::var("UPDATE_SNAPSHOTS").is_ok() {
+        // CI has to check the snapshots, not rewrite them
+        assert!(env::var("CI").is_err(), "UPDATE_SNAPSHOTS can't be used in CI");
+        fs::write(&path, &summary).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it", path.display()));
+    assert_eq!(
+        summary, expected,
+        "world generation changed for snapshot {}; if this is intentional, run with UPDATE_SNAPSHOTS=1",
+        name
+    );
+}
+
+#[test]
+fn default_world() {
+    assert_snapshot("default_world", WorldGenSettings::default());
+}
+
+#[test]
+fn small_world_with_land_percentage() {
+    assert_snapshot("small_world_with_land_percentage", WorldGenSettings {
+        seed: 42,
+        chunks_width: 2,
+        chunks_height: 1,
+        land_percentage: Some(0.4),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn small_world_with_tectonics_and_erosion() {
+    assert_snapshot("small_world_with_tectonics_and_erosion", WorldGenSettings {
+        seed: 7,
+        chunks_width: 2,
+        chunks_height: 1,
+        plate_count: 8,
+        hydraulic_erosion_iterations: 5000,
+        thermal_erosion_iterations: 5,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn small_world_wrapped() {
+    assert_snapshot("small_world_wrapped", WorldGenSettings {
+        seed: 42,
+        chunks_width: 2,
+        chunks_height: 1,
+        wrap_horizontal: true,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn generation_is_deterministic() {
+    let settings = WorldGenSettings {
+        seed: 99,
+        chunks_width: 1,
+        chunks_height: 1,
+        plate_count: 4,
+        hydraulic_erosion_iterations: 1000,
+        ..Default::default()
+    };
+    assert_eq!(summarize(&generate_world(settings.clone())), summarize(&generate_world(settings)));
+}
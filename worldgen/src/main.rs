@@ -0,0 +1,273 @@
+//! Generates worlds without running the game and exports them for previewing and analysis.
+//!
+//! For each seed this writes `heightmap.png`, `terrain.png`, `biomes.png`, `rivers.png`
+//! and a `world.json` dump of every tile into `<out>/<seed>/`.
+
+use game_plugin::{
+    hex::{HexCoord, HexDirection},
+    worldgen::{
+        WorldData, WorldGenSettings, generate_world,
+        climate::Biome,
+        rivers::OWNED_EDGES,
+        terrain::TerrainType,
+    },
+};
+use image::{Rgb, RgbImage};
+use serde::Serialize;
+use std::{env, fs, path::{Path, PathBuf}, process};
+
+const USAGE: &str = "Usage: worldgen [options]
+
+Options:
+    --seed <u32>             World seed (default 1234)
+    --count <n>              Number of consecutive seeds to generate (default 1)
+    --chunks-width <n>       Map width in chunks (default 6)
+    --chunks-height <n>      Map height in chunks (default 3)
+    --sea-level <f64>        Sea level (default 0.05)
+    --land-percentage <f64>  Fraction of land, overrides the sea level
+    --plates <n>             Number of tectonic plates (default 0)
+    --erosion <n>            Hydraulic erosion droplets (default 0)
+    --thermal-erosion <n>    Thermal erosion passes (default 0)
+    --wrap                   Connect the east and west edges of the map
+    --out <dir>              Output directory (default worldgen_output)
+    --help                   Print this message";
+
+struct Options {
+    settings: WorldGenSettings,
+    count: u32,
+    out: PathBuf,
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    let value = value.unwrap_or_else(|| fail(&format!("missing value for {}", flag)));
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("invalid value for {}: {}", flag, value)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(1);
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        settings: WorldGenSettings::default(),
+        count: 1,
+        out: PathBuf::from("worldgen_output"),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let settings = &mut options.settings;
+        match flag.as_str() {
+            "--seed" => settings.seed = parse_value(&flag, args.next()),
+            "--count" => options.count = parse_value(&flag, args.next()),
+            "--chunks-width" => settings.chunks_width = parse_value(&flag, args.next()),
+            "--chunks-height" => settings.chunks_height = parse_value(&flag, args.next()),
+            "--sea-level" => settings.sea_level = parse_value(&flag, args.next()),
+            "--land-percentage" => settings.land_percentage = Some(parse_value(&flag, args.next())),
+            "--plates" => settings.plate_count = parse_value(&flag, args.next()),
+            "--erosion" => settings.hydraulic_erosion_iterations = parse_value(&flag, args.next()),
+            "--thermal-erosion" => settings.thermal_erosion_iterations = parse_value(&flag, args.next()),
+            "--wrap" => settings.wrap_horizontal = true,
+            "--out" => options.out = parse_value(&flag, args.next()),
+            "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => fail(&format!("unknown option: {}", flag)),
+        }
+    }
+    options
+}
+
+fn terrain_color(terrain_type: TerrainType) -> Rgb<u8> {
+    match terrain_type {
+        TerrainType::DeepOcean => Rgb([24, 58, 122]),
+        TerrainType::Coast => Rgb([54, 108, 178]),
+        TerrainType::Lake => Rgb([72, 140, 204]),
+        TerrainType::Beach => Rgb([222, 206, 150]),
+        TerrainType::Plains => Rgb([104, 160, 72]),
+        TerrainType::Hills => Rgb([124, 134, 70]),
+        TerrainType::Mountains => Rgb([122, 110, 100]),
+        TerrainType::SnowPeaks => Rgb([240, 240, 246]),
+    }
+}
+
+fn biome_color(biome: Biome) -> Rgb<u8> {
+    match biome {
+        Biome::Water => Rgb([40, 80, 150]),
+        Biome::Ice => Rgb([230, 240, 250]),
+        Biome::Tundra => Rgb([160, 170, 150]),
+        Biome::Taiga => Rgb([60, 100, 80]),
+        Biome::Grassland => Rgb([150, 190, 90]),
+        Biome::TemperateForest => Rgb([60, 130, 60]),
+        Biome::TemperateRainforest => Rgb([30, 110, 70]),
+        Biome::Desert => Rgb([230, 210, 140]),
+        Biome::Savanna => Rgb([200, 190, 90]),
+        Biome::TropicalRainforest => Rgb([20, 90, 30]),
+    }
+}
+
+/// Builds an image with one pixel per tile, with north at the top
+fn tile_image(world: &WorldData, color: impl Fn(i32, i32) -> Rgb<u8>) -> RgbImage {
+    RgbImage::from_fn(world.width as u32, world.height as u32, |px, py| {
+        color(px as i32, world.height - 1 - py as i32)
+    })
+}
+
+fn heightmap_image(world: &WorldData) -> RgbImage {
+    tile_image(world, |x, y| {
+        let value = world.heightmap.get_value(x as usize, y as usize);
+        let shade = ((value * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0) as u8;
+        Rgb([shade, shade, shade])
+    })
+}
+
+/// Largest river flow along any edge of a tile
+fn tile_river_flow(world: &WorldData, x: i32, y: i32) -> u32 {
+    HexDirection::ALL
+        .iter()
+        .map(|&direction| world.river_flow(HexCoord::new(x, y), direction))
+        .max()
+        .unwrap()
+}
+
+fn rivers_image(world: &WorldData) -> RgbImage {
+    let max_flow = (0..world.width)
+        .flat_map(|x| (0..world.height).map(move |y| (x, y)))
+        .map(|(x, y)| tile_river_flow(world, x, y))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    tile_image(world, |x, y| {
+        let flow = tile_river_flow(world, x, y);
+        if world.tile(x, y).terrain_type.is_water() {
+            Rgb([20, 30, 60])
+        } else if flow > 0 {
+            let strength = 0.4 + 0.6 * (flow as f64 / max_flow as f64).sqrt();
+            Rgb([0, (120.0 * strength) as u8, (255.0 * strength) as u8])
+        } else {
+            Rgb([90, 90, 90])
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct TileRecord {
+    x: i32,
+    y: i32,
+    height: f64,
+    temperature: f64,
+    moisture: f64,
+    terrain: String,
+    biome: String,
+    /// River flow along the N, NE and SE edges. The other edges are in the records of the
+    /// neighbors on those sides.
+    rivers: [u32; OWNED_EDGES],
+    water_body: Option<u32>,
+    landmass: Option<u32>,
+    resource: Option<ResourceRecord>,
+}
+
+#[derive(Serialize)]
+struct ResourceRecord {
+    kind: String,
+    quantity: u32,
+}
+
+#[derive(Serialize)]
+struct WaterBodyRecord {
+    id: u32,
+    kind: String,
+    size: usize,
+}
+
+#[derive(Serialize)]
+struct LandmassRecord {
+    id: u32,
+    kind: String,
+    area: usize,
+    coastline_length: usize,
+}
+
+#[derive(Serialize)]
+struct WorldRecord {
+    seed: u32,
+    width: i32,
+    height: i32,
+    sea_level: f64,
+    water_bodies: Vec<WaterBodyRecord>,
+    landmasses: Vec<LandmassRecord>,
+    tiles: Vec<TileRecord>,
+}
+
+fn world_record(world: &WorldData) -> WorldRecord {
+    let mut tiles = Vec::with_capacity((world.width * world.height) as usize);
+    for y in 0..world.height {
+        for x in 0..world.width {
+            let hex_tile = world.tile(x, y);
+            tiles.push(TileRecord {
+                x,
+                y,
+                height: world.heightmap.get_value(x as usize, y as usize),
+                temperature: world.temperature_map.get_value(x as usize, y as usize),
+                moisture: world.moisture_map.get_value(x as usize, y as usize),
+                terrain: format!("{:?}", hex_tile.terrain_type),
+                biome: format!("{:?}", hex_tile.biome),
+                rivers: hex_tile.rivers,
+                water_body: hex_tile.water_body.map(|id| id.0),
+                landmass: hex_tile.landmass.map(|id| id.0),
+                resource: hex_tile.resource.map(|deposit| ResourceRecord {
+                    kind: format!("{:?}", deposit.resource),
+                    quantity: deposit.quantity,
+                }),
+            });
+        }
+    }
+    WorldRecord {
+        seed: world.settings.seed,
+        width: world.width,
+        height: world.height,
+        sea_level: world.sea_level,
+        water_bodies: world.water_bodies.bodies.iter().map(|water_body| WaterBodyRecord {
+            id: water_body.id.0,
+            kind: format!("{:?}", water_body.kind),
+            size: water_body.size,
+        }).collect(),
+        landmasses: world.landmasses.landmasses.iter().map(|landmass| LandmassRecord {
+            id: landmass.id.0,
+            kind: format!("{:?}", landmass.kind),
+            area: landmass.area,
+            coastline_length: landmass.coastline_length,
+        }).collect(),
+        tiles,
+    }
+}
+
+fn export_world(world: &WorldData, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+    heightmap_image(world).save(dir.join("heightmap.png"))?;
+    tile_image(world, |x, y| terrain_color(world.tile(x, y).terrain_type)).save(dir.join("terrain.png"))?;
+    tile_image(world, |x, y| biome_color(world.tile(x, y).biome)).save(dir.join("biomes.png"))?;
+    rivers_image(world).save(dir.join("rivers.png"))?;
+    let file = fs::File::create(dir.join("world.json"))?;
+    serde_json::to_writer(std::io::BufWriter::new(file), &world_record(world))?;
+    Ok(())
+}
+
+fn main() {
+    let options = parse_options();
+    for index in 0..options.count {
+        let mut settings = options.settings.clone();
+        settings.seed = settings.seed.wrapping_add(index);
+        let seed = settings.seed;
+        let world = generate_world(settings);
+        let dir = options.out.join(seed.to_string());
+        if let Err(error) = export_world(&world, &dir) {
+            eprintln!("Failed to export seed {}: {}", seed, error);
+            process::exit(1);
+        }
+        println!("Exported seed {} to {}", seed, dir.display());
+    }
+}